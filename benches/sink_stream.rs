@@ -59,6 +59,24 @@ pub fn z_test_message() -> TestMessage {
     )
 }
 
+/// A `Z` message of about 11 KiB, with a long string and many nested messages.
+pub fn large_z_test_message() -> TestMessage {
+    TestMessage::Z(
+        1,
+        2,
+        3,
+        4,
+        "Hello".repeat(1024),
+        (0..256).map(|_| z_test_message()).collect(),
+        Box::new(z_test_message()),
+    )
+}
+
+/// An `E` message of 64 KiB, a single large field.
+pub fn large_e_test_message() -> TestMessage {
+    TestMessage::E("Hello, World!!!!".repeat(4096))
+}
+
 pub fn test_messages() -> Vec<TestMessage> {
     std::vec![
         TestMessage::A(100),
@@ -179,6 +197,25 @@ mod tokio_codec {
     }
 }
 
+mod tokio_encode {
+    use the_bridge::Codec;
+    use tokio_util::{bytes::BytesMut, codec::Encoder};
+
+    use crate::TestMessage;
+
+    /// Encodes `items` into a fresh buffer, as a `FramedWrite` does after a flush of a large write.
+    pub fn bench(items: &[TestMessage]) -> BytesMut {
+        let mut codec = Codec::<TestMessage>::new().by_ref();
+        let mut dst = BytesMut::new();
+
+        for item in items {
+            codec.encode(item, &mut dst).unwrap();
+        }
+
+        dst
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let duplex_size: usize = 1024;
 
@@ -197,6 +234,26 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("tokio_codec", |b| {
         b.iter(|| tokio_codec::bench(black_box(test_messages.clone()), black_box(duplex_size)))
     });
+
+    let large_z_test_messages = Arc::new(vec![large_z_test_message(); 1000]);
+
+    c.bench_function("tokio_codec_large_z", |b| {
+        b.iter(|| {
+            tokio_codec::bench(
+                black_box(large_z_test_messages.clone()),
+                black_box(duplex_size),
+            )
+        })
+    });
+    c.bench_function("tokio_encode_large_z", |b| {
+        b.iter(|| tokio_encode::bench(black_box(&large_z_test_messages)))
+    });
+
+    let large_e_test_messages = vec![large_e_test_message(); 1000];
+
+    c.bench_function("tokio_encode_large_e", |b| {
+        b.iter(|| tokio_encode::bench(black_box(&large_e_test_messages)))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -22,9 +22,9 @@ use esp_wifi::{
     },
     EspWifiInitFor,
 };
-use futures::{pin_mut, SinkExt, StreamExt};
-use the_bridge::demo::DemoMessage;
-use the_bridge::Codec;
+use futures::pin_mut;
+use the_bridge::demo::{DeviceMessage, ServerMessage};
+use the_bridge::{Codec, MaxEncodedSize, MessageTransport, Transport};
 
 // When you are okay with using a nightly compiler it's better to use https://docs.rs/static_cell/2.1.0/static_cell/macro.make_static.html
 macro_rules! mk_static {
@@ -121,72 +121,75 @@ async fn main(spawner: Spawner) -> ! {
 
         let (tcp_reader, tcp_writer) = socket.split();
 
-        let read_buf: &mut [u8] = &mut [0; 128];
-        let write_buf: &mut [u8] = &mut [0; 128];
+        // `MAX_SIZE` is the largest frame with the framing and configuration of `Codec::new`,
+        // which both codecs below use, so no message can outgrow these buffers.
+        let read_buf: &mut [u8] = &mut [0; ServerMessage::MAX_SIZE];
+        let write_buf: &mut [u8] = &mut [0; DeviceMessage::MAX_SIZE];
 
+        // Frames that can never fit into `read_buf` are reported as errors instead of stalling the stream.
         let framed_read = FramedRead::new(
             Compat::new(tcp_reader),
-            Codec::<DemoMessage>::new(),
+            Codec::<DeviceMessage, ServerMessage>::new().with_buffer_capacity(read_buf.len()),
             read_buf,
         )
         .into_stream();
 
         let framed_write = FramedWrite::new(
             Compat::new(tcp_writer),
-            Codec::<DemoMessage>::new(),
+            Codec::<DeviceMessage, ServerMessage>::new(),
             write_buf,
         )
         .into_sink();
 
         pin_mut!(framed_read, framed_write);
-        loop {
-            let time_fut = Timer::after(Duration::from_millis(3_000));
-            let read_fut = framed_read.next();
-
-            match embassy_futures::select::select(time_fut, read_fut).await {
-                Either::First(_) => {
-                    let message = DemoMessage::Measurement(1024);
-                    log::info!("Sending message: {:?}", message);
-
-                    match framed_write.send(message).await {
-                        Ok(_) => {
-                            log::info!("Message sent");
-                        }
-                        Err(e) => {
-                            log::error!("Error: {:?}", e);
-                            break;
-                        }
+
+        run(&mut Transport::new(framed_read, framed_write)).await;
+    }
+}
+
+/// Sends a measurement every 3 seconds and answers the pings of the server.
+///
+/// Written against [`MessageTransport`], so it does not depend on the backend.
+async fn run<T>(transport: &mut T)
+where
+    T: MessageTransport<DeviceMessage, ServerMessage>,
+    T::SendError: core::fmt::Debug,
+    T::RecvError: core::fmt::Debug,
+{
+    loop {
+        let time_fut = Timer::after(Duration::from_millis(3_000));
+        let read_fut = transport.recv();
+
+        let message = match embassy_futures::select::select(time_fut, read_fut).await {
+            Either::First(_) => DeviceMessage::Measurement(1024),
+            Either::Second(read_result) => match read_result {
+                Ok(Some(message)) => {
+                    log::info!("Received message: {:?}", message);
+
+                    match message {
+                        ServerMessage::Ping(u) => DeviceMessage::Pong(u),
                     }
                 }
+                Err(e) => {
+                    log::error!("Error: {:?}, reconnecting", e);
+                    break;
+                }
+                Ok(None) => {
+                    log::info!("Connection closed");
+                    break;
+                }
+            },
+        };
 
-                Either::Second(read_result) => match read_result {
-                    Some(Ok(message)) => {
-                        log::info!("Received message: {:?}", message);
-
-                        if let DemoMessage::Ping(u) = message {
-                            let message = DemoMessage::Pong(u);
-                            log::info!("Sending message: {:?}", message);
-
-                            match framed_write.send(message).await {
-                                Ok(_) => {
-                                    log::info!("Message sent");
-                                }
-                                Err(e) => {
-                                    log::error!("Error: {:?}", e);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Some(Err(e)) => {
-                        log::error!("Error: {:?}", e);
-                        break;
-                    }
-                    None => {
-                        log::info!("Connection closed");
-                        break;
-                    }
-                },
+        log::info!("Sending message: {:?}", message);
+
+        match transport.send(message).await {
+            Ok(_) => {
+                log::info!("Message sent");
+            }
+            Err(e) => {
+                log::error!("Error: {:?}", e);
+                break;
             }
         }
     }
@@ -5,8 +5,10 @@
 //! ```
 //!
 
-use futures::{SinkExt, StreamExt};
-use the_bridge::{demo::DemoMessage, Codec};
+use the_bridge::{
+    demo::{DeviceMessage, ServerMessage},
+    Codec, MessageTransport, Transport,
+};
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 #[tokio::main]
@@ -33,65 +35,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tokio::spawn(async move {
             let (reader, writer) = socket.into_split();
 
-            let mut stream = FramedRead::new(reader, Codec::<DemoMessage>::new());
-            let mut sink = FramedWrite::new(writer, Codec::<DemoMessage>::new());
+            let transport = Transport::new(
+                FramedRead::new(reader, Codec::<ServerMessage, DeviceMessage>::new()),
+                FramedWrite::new(writer, Codec::<ServerMessage, DeviceMessage>::new()),
+            );
 
-            let mut ping_count = 0;
-            loop {
-                tokio::select! {
-                    _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {
-                        // We didn't receive a message in 5 seconds, send a ping!, are you ok?
-                        let ping_message = DemoMessage::Ping(ping_count);
-                        ping_count += 1;
+            serve(transport).await;
 
-                        match sink.send(ping_message).await {
-                            Ok(_) => {
-                                tracing::info!("Sent ping");
-                            }
-                            Err(error) => {
-                                tracing::error!(?error, "Error sending ping");
-                                break;
-                            }
-                        }
-                    }
-                    message = stream.next() => {
-                        match message {
-                            None => {
-                                break;
-                            }
-                            Some(Ok(message)) => {
-                                tracing::info!(?message, "Received message");
+            tracing::debug!("Disconnected")
+        });
+    }
+}
+
+/// Pings the device whenever it has been silent for 5 seconds.
+///
+/// Written against [`MessageTransport`], so it does not depend on the backend.
+async fn serve<T>(mut transport: T)
+where
+    T: MessageTransport<ServerMessage, DeviceMessage>,
+    T::SendError: std::fmt::Debug,
+    T::RecvError: std::fmt::Debug,
+{
+    let mut ping_count = 0;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {
+                // We didn't receive a message in 5 seconds, send a ping!, are you ok?
+                let ping_message = ServerMessage::Ping(ping_count);
+                ping_count += 1;
 
-                                match message {
-                                    DemoMessage::Ping(u) => {
-                                        let response = DemoMessage::Pong(u);
+                match transport.send(ping_message).await {
+                    Ok(_) => {
+                        tracing::info!("Sent ping");
+                    }
+                    Err(error) => {
+                        tracing::error!(?error, "Error sending ping");
+                        break;
+                    }
+                }
+            }
+            message = transport.recv() => {
+                match message {
+                    Ok(None) => {
+                        break;
+                    }
+                    Ok(Some(message)) => {
+                        tracing::info!(?message, "Received message");
 
-                                        match sink.send(response).await {
-                                            Ok(_) => {
-                                                tracing::info!("Sent pong");
-                                            }
-                                            Err(error) => {
-                                                tracing::error!(?error, "Error sending pong");
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    DemoMessage::Pong(_) => {
-                                    }
-                                    DemoMessage::Measurement(_) => {
-                                    }
-                                }
+                        match message {
+                            DeviceMessage::Pong(_) => {
                             }
-                            Some(Err(error)) => {
-                                tracing::error!(?error, "Error reading message");
-                                break;
+                            DeviceMessage::Measurement(_) => {
                             }
                         }
                     }
+                    Err(error) => {
+                        tracing::error!(?error, "Error reading message");
+                        break;
+                    }
                 }
             }
-
-            tracing::debug!("Disconnected")
-        });
+        }
     }
 }
@@ -0,0 +1,88 @@
+//! Zero-copy decoding of messages that borrow from the read buffer.
+
+use bincode::de::BorrowDecode;
+
+/// A message type that can borrow from the buffer it is decoded from.
+///
+/// The codec type cannot carry the lifetime of a buffer it does not own yet, so
+/// `Codec<Tx, M>` names the message by its `'static` form and decodes `M::Message<'buf>`.
+///
+/// ```
+/// use the_bridge::BorrowMessage;
+///
+/// #[derive(bincode::BorrowDecode)]
+/// struct Telemetry<'a> {
+///     id: u32,
+///     blob: &'a [u8],
+/// }
+///
+/// impl BorrowMessage for Telemetry<'static> {
+///     type Message<'buf> = Telemetry<'buf>;
+/// }
+/// ```
+pub trait BorrowMessage {
+    type Message<'buf>: BorrowDecode<'buf>;
+}
+
+impl BorrowMessage for &'static [u8] {
+    type Message<'buf> = &'buf [u8];
+}
+
+impl BorrowMessage for &'static str {
+    type Message<'buf> = &'buf str;
+}
+
+#[cfg(feature = "tokio")]
+pub use self::bytes::Borrowed;
+
+#[cfg(feature = "tokio")]
+mod bytes {
+    use core::marker::PhantomData;
+
+    use bincode::config::{Config, Configuration};
+    use tokio_util::bytes::Bytes;
+
+    use super::BorrowMessage;
+
+    /// A frame yielded by the tokio decoder of `Codec<Tx, Borrowed<M>>`.
+    ///
+    /// It keeps the payload alive, so that messages decoded from it can borrow from it.
+    pub struct Borrowed<M, C = Configuration> {
+        payload: Bytes,
+        config: C,
+        _phantom: PhantomData<M>,
+    }
+
+    impl<M, C> Borrowed<M, C> {
+        #[inline]
+        pub(crate) const fn new(payload: Bytes, config: C) -> Self {
+            Self {
+                payload,
+                config,
+                _phantom: PhantomData,
+            }
+        }
+
+        #[inline]
+        pub const fn payload(&self) -> &Bytes {
+            &self.payload
+        }
+
+        #[inline]
+        pub fn into_payload(self) -> Bytes {
+            self.payload
+        }
+    }
+
+    impl<M, C> Borrowed<M, C>
+    where
+        M: BorrowMessage,
+        C: Config,
+    {
+        /// Decodes the message from the payload. Every call decodes it again.
+        pub fn message(&self) -> Result<M::Message<'_>, bincode::error::DecodeError> {
+            bincode::borrow_decode_from_slice(&self.payload, self.config)
+                .map(|(message, _)| message)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Encoding messages without giving them up.
+
+/// Wraps a [`Codec`](crate::Codec) so that it encodes messages by reference, as `&Tx` or as an
+/// `EncodedFrame`, instead of by value. Decoding is left unchanged.
+///
+/// A codec that accepted both would make sinks over it accept both, and calls like
+/// `framed_write.close()` would need the item type spelled out.
+pub struct ByRef<T> {
+    inner: T,
+}
+
+impl<T> ByRef<T> {
+    #[inline]
+    pub const fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
@@ -0,0 +1,146 @@
+//! Payloads encoded as CBOR with minicbor, readable by CBOR libraries of other languages.
+
+use minicbor::{decode, encode, Decode, Encode};
+
+use crate::serializer::{BufferFull, Deserializer, Serializer, Writer};
+
+/// The CBOR format, for messages implementing minicbor's `Encode` and `Decode`.
+///
+/// Frames are the same as with bincode, only the payloads differ. Every payload is a single
+/// CBOR data item.
+///
+/// ```
+/// use the_bridge::{Cbor, Codec};
+///
+/// #[derive(minicbor::Encode, minicbor::Decode)]
+/// struct Reading {
+///     #[n(0)]
+///     sensor: u8,
+///     #[n(1)]
+///     value: f32,
+/// }
+///
+/// let codec = Codec::<Reading, Reading, Cbor>::cbor();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cbor;
+
+struct CborWriter<'a, W>(&'a mut W);
+
+impl<W: Writer> encode::Write for CborWriter<'_, W> {
+    type Error = BufferFull;
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(buf)
+    }
+}
+
+impl<T> Serializer<T> for Cbor
+where
+    T: Encode<()> + ?Sized,
+{
+    type Error = encode::Error<BufferFull>;
+
+    fn serialize<W: Writer>(&self, item: &T, dst: &mut W) -> Result<(), Self::Error> {
+        minicbor::encode(item, CborWriter(dst))
+    }
+}
+
+impl<T> Deserializer<T> for Cbor
+where
+    T: for<'b> Decode<'b, ()>,
+{
+    type Error = decode::Error;
+
+    fn deserialize(&self, src: &[u8]) -> Result<T, Self::Error> {
+        minicbor::decode(src)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::{string::String, vec::Vec};
+
+    use super::Cbor;
+    use crate::{codec::Codec, frame_decoder::FrameDecoder};
+
+    #[derive(Debug, Clone, PartialEq, minicbor::Encode, minicbor::Decode)]
+    enum Message {
+        #[n(0)]
+        Ping(#[n(0)] u32),
+        #[n(1)]
+        Log {
+            #[n(0)]
+            level: u8,
+            #[n(1)]
+            text: String,
+        },
+    }
+
+    #[test]
+    fn payload_is_plain_cbor() {
+        let codec = Codec::<Message, Message, _>::cbor();
+
+        let mut dst = [0_u8; 16];
+
+        let size = codec.encode_payload(&Message::Ping(10), &mut dst).unwrap();
+
+        // The variant index and the array of its fields, as any CBOR library reads them.
+        assert_eq!(dst[..size], [0x82, 0x00, 0x81, 0x0a]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn framed() {
+        use futures::{executor::block_on, SinkExt, StreamExt};
+        use tokio_util::codec::{FramedRead, FramedWrite};
+
+        let mut framed_write = FramedWrite::new(Vec::new(), Codec::<Message, Message, _>::cbor());
+
+        block_on(framed_write.send(Message::Ping(7))).unwrap();
+
+        let bytes = framed_write.into_inner();
+
+        let mut framed_read = FramedRead::new(&bytes[..], Codec::<Message, Message, _>::cbor());
+
+        assert_eq!(
+            block_on(framed_read.next()).unwrap().unwrap(),
+            Message::Ping(7)
+        );
+    }
+
+    #[test]
+    fn frame_decoder() {
+        let messages = std::vec![
+            Message::Ping(1),
+            Message::Log {
+                level: 2,
+                text: String::from("Hello"),
+            },
+        ];
+
+        let codec = Codec::<Message, Message, Cbor>::cbor();
+
+        let mut bytes = Vec::new();
+
+        for message in &messages {
+            let mut payload = [0_u8; 32];
+
+            let size = codec.encode_payload(message, &mut payload).unwrap();
+
+            bytes.extend_from_slice(&(size as u32 + 4).to_be_bytes());
+            bytes.extend_from_slice(&payload[..size]);
+        }
+
+        let mut decoder = FrameDecoder::<_, 64>::new(codec);
+
+        decoder.feed(&bytes);
+
+        let decoded: Vec<_> = decoder.messages().map(Result::unwrap).collect();
+
+        assert_eq!(decoded, messages);
+    }
+}
@@ -0,0 +1,153 @@
+//! Consistent Overhead Byte Stuffing.
+
+/// Maximum number of bytes COBS adds to `len` bytes of data, not counting the delimiter.
+#[inline]
+pub(crate) const fn max_overhead(len: usize) -> usize {
+    1 + len / 254
+}
+
+/// Encodes `buf[..len]` in place and returns the length of the encoded data.
+///
+/// `buf` must hold at least `len + max_overhead(len)` bytes.
+/// The encoded data contains no zero bytes and is not terminated.
+pub(crate) fn encode_in_place(buf: &mut [u8], len: usize) -> usize {
+    let offset = max_overhead(len);
+
+    buf.copy_within(..len, offset);
+
+    // Reading at `offset + i` always stays ahead of writing at `out`.
+    let mut code_index = 0;
+    let mut out = 1;
+    let mut code = 1_u8;
+
+    for i in offset..offset + len {
+        let byte = buf[i];
+
+        if byte == 0 {
+            buf[code_index] = code;
+            code_index = out;
+            out += 1;
+            code = 1;
+
+            continue;
+        }
+
+        buf[out] = byte;
+        out += 1;
+        code += 1;
+
+        if code == 0xFF {
+            buf[code_index] = code;
+            code_index = out;
+            out += 1;
+            code = 1;
+        }
+    }
+
+    buf[code_index] = code;
+
+    out
+}
+
+/// Decodes `buf` in place and returns the length of the decoded data.
+///
+/// Returns `None` if `buf` is not valid COBS data.
+pub(crate) fn decode_in_place(buf: &mut [u8]) -> Option<usize> {
+    let len = buf.len();
+
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < len {
+        let code = buf[read];
+
+        if code == 0 {
+            return None;
+        }
+
+        read += 1;
+
+        let end = read + code as usize - 1;
+
+        if end > len {
+            return None;
+        }
+
+        while read < end {
+            if buf[read] == 0 {
+                return None;
+            }
+
+            buf[write] = buf[read];
+            write += 1;
+            read += 1;
+        }
+
+        if code != 0xFF && read < len {
+            buf[write] = 0;
+            write += 1;
+        }
+    }
+
+    Some(write)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn round_trip(data: &[u8]) {
+        let mut buf = std::vec![0xAA_u8; data.len() + max_overhead(data.len())];
+        buf[..data.len()].copy_from_slice(data);
+
+        let encoded_len = encode_in_place(&mut buf, data.len());
+
+        assert!(encoded_len <= data.len() + max_overhead(data.len()));
+        assert!(buf[..encoded_len].iter().all(|&b| b != 0));
+
+        let decoded_len = decode_in_place(&mut buf[..encoded_len]).unwrap();
+
+        assert_eq!(&buf[..decoded_len], data);
+    }
+
+    #[test]
+    fn known_vectors() {
+        let mut buf = [0_u8; 8];
+        buf[..4].copy_from_slice(&[0x11, 0x22, 0x00, 0x33]);
+
+        let encoded_len = encode_in_place(&mut buf, 4);
+
+        assert_eq!(&buf[..encoded_len], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+
+        let mut buf = [0_u8; 4];
+        buf[..2].copy_from_slice(&[0x00, 0x00]);
+
+        let encoded_len = encode_in_place(&mut buf, 2);
+
+        assert_eq!(&buf[..encoded_len], &[0x01, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(&[]);
+        round_trip(&[0]);
+        round_trip(&[1, 2, 3]);
+
+        for len in [253, 254, 255, 508, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 7) as u8).collect();
+            round_trip(&data);
+
+            let data: Vec<u8> = (0..len).map(|i| (i % 255) as u8 + 1).collect();
+            round_trip(&data);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode_in_place(&mut [0x05, 0x11]), None);
+        assert_eq!(decode_in_place(&mut [0x03, 0x00, 0x11]), None);
+    }
+}
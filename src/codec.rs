@@ -1,20 +1,273 @@
 use core::marker::PhantomData;
 
-pub struct Codec<M> {
-    _phantom: PhantomData<M>,
+use bincode::config::Configuration;
+
+use crate::{
+    by_ref::ByRef,
+    frame::{Framing, Skipped},
+    max_size::{self, MaxEncodedSize},
+    raw::{Raw, RawFrame},
+    recovering::Recovering,
+    serializer::{Deserializer, Serializer, SliceWriter},
+};
+
+pub struct Codec<Tx, Rx = Tx, C = Configuration> {
+    framing: Framing,
+    buffer_capacity: usize,
+    dropped_frames: usize,
+    skipped_bytes: usize,
+    /// Bytes at the start of a fixed read buffer that were skipped but not consumed yet.
+    skip_offset: usize,
+    config: C,
+    _phantom: PhantomData<(Tx, Rx)>,
 }
 
-impl<M> Codec<M> {
+impl<Tx, Rx> Codec<Tx, Rx> {
     #[inline]
     pub const fn new() -> Self {
+        Self::with_config(bincode::config::standard())
+    }
+
+    /// Returns a zeroed buffer of `N` bytes to read frames of `Rx` into, like the one of
+    /// `cody_c::FramedRead`.
+    ///
+    /// Fails to compile if the largest frame of `Rx` does not fit. See [`max_size::buffer`].
+    #[inline]
+    pub const fn read_buffer<const N: usize>() -> [u8; N]
+    where
+        Rx: MaxEncodedSize,
+    {
+        max_size::buffer::<Rx, N>()
+    }
+
+    /// Returns a zeroed buffer of `N` bytes to encode frames of `Tx` into, like the one of
+    /// `cody_c::FramedWrite`.
+    ///
+    /// Fails to compile if the largest frame of `Tx` does not fit. See [`max_size::buffer`].
+    #[inline]
+    pub const fn write_buffer<const N: usize>() -> [u8; N]
+    where
+        Tx: MaxEncodedSize,
+    {
+        max_size::buffer::<Tx, N>()
+    }
+}
+
+impl Codec<RawFrame, RawFrame, Raw> {
+    /// Creates a codec that forwards payloads without decoding them.
+    ///
+    /// See [`RawFrame`].
+    #[inline]
+    pub const fn raw() -> Self {
+        Self::with_config(Raw)
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl<Tx, Rx> Codec<Tx, Rx, crate::postcard::Postcard> {
+    /// Creates a codec that encodes and decodes messages with postcard.
+    #[inline]
+    pub const fn postcard() -> Self {
+        Self::with_config(crate::postcard::Postcard)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<Tx, Rx> Codec<Tx, Rx, crate::cbor::Cbor> {
+    /// Creates a codec that encodes and decodes messages as CBOR.
+    #[inline]
+    pub const fn cbor() -> Self {
+        Self::with_config(crate::cbor::Cbor)
+    }
+}
+
+impl<Tx, Rx, C> Codec<Tx, Rx, C>
+where
+    C: Copy,
+{
+    /// Creates a codec that encodes and decodes messages with the given format,
+    /// like a bincode configuration.
+    ///
+    /// ```
+    /// use the_bridge::Codec;
+    ///
+    /// let codec = Codec::<u32, u32, _>::with_config(
+    ///     bincode::config::legacy()
+    ///         .with_big_endian()
+    ///         .with_limit::<1024>(),
+    /// );
+    /// ```
+    #[inline]
+    pub const fn with_config(config: C) -> Self {
         Self {
+            framing: Framing::new(),
+            buffer_capacity: usize::MAX,
+            dropped_frames: 0,
+            skipped_bytes: 0,
+            skip_offset: 0,
+            config,
             _phantom: PhantomData,
         }
     }
+
+    /// Replaces the wire format of the frames, including their maximum size.
+    #[inline]
+    pub const fn with_framing(self, framing: Framing) -> Self {
+        Self { framing, ..self }
+    }
+
+    /// Limits the size of a frame, including its header.
+    ///
+    /// Frames announcing a larger size are rejected before their payload is buffered,
+    /// and messages that would encode into a larger frame are refused.
+    #[inline]
+    pub const fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            framing: self.framing.with_max_frame_size(max_frame_size),
+            ..self
+        }
+    }
+
+    /// Tells the codec the capacity of the buffer it decodes from.
+    ///
+    /// Readers with a fixed buffer, like `cody_c::FramedRead`, can never complete a frame that
+    /// does not fit into it. Such frames are reported as an error instead of waiting forever.
+    #[inline]
+    pub const fn with_buffer_capacity(self, buffer_capacity: usize) -> Self {
+        Self {
+            buffer_capacity,
+            ..self
+        }
+    }
+
+    /// Yields messages that fail to decode as `Err` items instead of failing the stream.
+    ///
+    /// See [`Recovering`].
+    #[inline]
+    pub const fn recovering(self) -> Recovering<Self> {
+        Recovering::new(self)
+    }
+
+    /// Encodes messages by reference instead of by value.
+    ///
+    /// See [`ByRef`].
+    #[inline]
+    pub const fn by_ref(self) -> ByRef<Self> {
+        ByRef::new(self)
+    }
 }
 
-impl<M> Default for Codec<M> {
+impl<Tx, Rx, C> Codec<Tx, Rx, C>
+where
+    C: Serializer<Tx>,
+{
+    /// Encodes `item` into `dst` as a payload, as forwarded by `Codec::raw`.
+    ///
+    /// See [`RawFrame`].
+    pub fn encode_payload(&self, item: &Tx, dst: &mut [u8]) -> Result<usize, C::Error> {
+        let mut writer = SliceWriter::new(dst);
+
+        self.config.serialize(item, &mut writer)?;
+
+        Ok(writer.len())
+    }
+
+    /// Returns the number of bytes of the payload of `item`, without encoding it anywhere.
+    ///
+    /// The frame adds its header and, depending on the framing, a trailer.
+    #[inline]
+    pub fn encoded_len(&self, item: &Tx) -> Result<usize, C::Error> {
+        self.config.encoded_len(item)
+    }
+}
+
+impl<Tx, Rx, C> Codec<Tx, Rx, C>
+where
+    C: Deserializer<Rx>,
+{
+    /// Decodes a payload yielded by `Codec::raw`.
+    ///
+    /// See [`RawFrame`].
+    pub fn decode_payload(&self, payload: &[u8]) -> Result<Rx, C::Error> {
+        self.config.deserialize(payload)
+    }
+}
+
+impl<Tx, Rx, C> Codec<Tx, Rx, C> {
+    #[inline]
+    pub const fn framing(&self) -> &Framing {
+        &self.framing
+    }
+
+    #[inline]
+    pub const fn max_frame_size(&self) -> usize {
+        self.framing.max_frame_size()
+    }
+
+    #[inline]
+    pub const fn buffer_capacity(&self) -> usize {
+        self.buffer_capacity
+    }
+
+    #[inline]
+    pub const fn config(&self) -> &C {
+        &self.config
+    }
+
+    /// Number of corrupted frames the decoder has dropped so far.
+    #[inline]
+    pub const fn dropped_frames(&self) -> usize {
+        self.dropped_frames
+    }
+
+    /// Number of bytes the decoder has discarded so far while looking for a valid frame.
+    #[inline]
+    pub const fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes
+    }
+
+    pub(crate) fn record_skip(&mut self, len: usize, skipped: Skipped) {
+        match skipped {
+            Skipped::Padding => {}
+            Skipped::Frame => {
+                self.dropped_frames += 1;
+                self.skipped_bytes += len;
+            }
+            Skipped::Garbage => {
+                self.skipped_bytes += len;
+            }
+        }
+    }
+
+    #[inline]
+    #[cfg_attr(not(feature = "cody-c"), allow(dead_code))]
+    pub(crate) fn skip_offset(&self) -> usize {
+        self.skip_offset
+    }
+
+    #[inline]
+    #[cfg_attr(not(feature = "cody-c"), allow(dead_code))]
+    pub(crate) fn set_skip_offset(&mut self, skip_offset: usize) {
+        self.skip_offset = skip_offset;
+    }
+}
+
+impl<Tx, Rx> Default for Codec<Tx, Rx> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Formats the framing and the counters of the codec, not its format.
+#[cfg(feature = "defmt")]
+impl<Tx, Rx, C> defmt::Format for Codec<Tx, Rx, C> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Codec {{ framing: {}, dropped_frames: {=usize}, skipped_bytes: {=usize} }}",
+            self.framing,
+            self.dropped_frames,
+            self.skipped_bytes,
+        )
+    }
+}
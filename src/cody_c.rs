@@ -1,76 +1,547 @@
-use cody_c::{DecoderOwned, Encoder};
+//! `cody_c` integration and its errors.
+//!
+//! # Skipped bytes
+//!
+//! cody-c's decoders only consume bytes together with an item. Bytes the framing skips, like a
+//! corrupted or oversized COBS frame or noise in front of a sync word, stay at the start of the
+//! read buffer until the next frame is decoded, and the codec remembers how many there are:
+//!
+//! - Skipped bytes and the frame after them must fit into the read buffer together. Otherwise
+//!   decoding fails with [`DecodeError::FrameExceedsBuffer`] instead of resynchronizing.
+//! - A codec must only decode from the one buffer it was used with. Moved to another buffer
+//!   while bytes are skipped, it would skip bytes of that buffer instead.
+//!
+//! The tokio, std and embedded-io readers and `FrameDecoder` drop skipped bytes right away.
 
-use crate::codec::Codec;
+use core::{convert::Infallible, fmt, ops::Range};
+
+use cody_c::{Decoder, DecoderOwned, Encoder};
+
+use crate::{
+    borrowed::BorrowMessage,
+    by_ref::ByRef,
+    codec::Codec,
+    error::BridgeError,
+    frame::{self, Decoded},
+    raw::{Raw, RawFrame},
+    recovering::Recovering,
+    serializer::{Deserializer, Serializer, SliceWriter},
+};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
-pub enum EncodeError {
+pub enum EncodeError<E = bincode::error::EncodeError> {
     InputBufferTooSmall,
-    Encode(bincode::error::EncodeError),
+    Encode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
     MessageTooBig,
+    FrameTooLarge { size: usize, max: usize },
+    FramingMismatch,
+}
+
+/// Writes a frame to the start of `dst` and returns its size.
+///
+/// `write_payload` gets the space between the header and the trailer and returns the size
+/// of the payload it wrote there.
+fn encode_with<Tx, Rx, C, E>(
+    codec: &Codec<Tx, Rx, C>,
+    dst: &mut [u8],
+    write_payload: impl FnOnce(&mut [u8]) -> Result<usize, EncodeError<E>>,
+) -> Result<usize, EncodeError<E>> {
+    let header_size = codec.framing().max_header_len();
+    let reserved_size = codec.framing().reserved_after(dst.len());
+
+    if dst.len() < header_size + reserved_size {
+        return Err(EncodeError::InputBufferTooSmall);
+    }
+
+    let payload_end = dst.len() - reserved_size;
+
+    let message_size = write_payload(&mut dst[header_size..payload_end])?;
+
+    let packet_size = codec.framing().encode_frame(dst, message_size)?;
+
+    Ok(packet_size)
 }
 
-impl<M> Encoder<M> for Codec<M>
+fn encode_item<Tx, Rx, C>(
+    codec: &Codec<Tx, Rx, C>,
+    item: &Tx,
+    dst: &mut [u8],
+) -> Result<usize, EncodeError<C::Error>>
 where
-    M: bincode::Encode,
+    C: Serializer<Tx>,
 {
+    encode_with(codec, dst, |dst| {
+        let mut writer = SliceWriter::new(dst);
+
+        codec
+            .config()
+            .serialize(item, &mut writer)
+            .map_err(EncodeError::Encode)?;
+
+        Ok(writer.len())
+    })
+}
+
+impl<Tx, Rx, C> Encoder<Tx> for Codec<Tx, Rx, C>
+where
+    C: Serializer<Tx>,
+{
+    type Error = EncodeError<C::Error>;
+
+    fn encode(&mut self, item: Tx, dst: &mut [u8]) -> Result<usize, Self::Error> {
+        encode_item(self, &item, dst)
+    }
+}
+
+impl<'a, Tx, Rx, C> Encoder<&'a Tx> for ByRef<Codec<Tx, Rx, C>>
+where
+    C: Serializer<Tx>,
+{
+    type Error = EncodeError<C::Error>;
+
+    fn encode(&mut self, item: &'a Tx, dst: &mut [u8]) -> Result<usize, Self::Error> {
+        encode_item(self.inner(), item, dst)
+    }
+}
+
+impl<'a> Encoder<&'a [u8]> for Codec<RawFrame, RawFrame, Raw> {
+    type Error = EncodeError<Infallible>;
+
+    fn encode(&mut self, item: &'a [u8], dst: &mut [u8]) -> Result<usize, Self::Error> {
+        encode_with(self, dst, |dst| {
+            dst.get_mut(..item.len())
+                .ok_or(EncodeError::InputBufferTooSmall)?
+                .copy_from_slice(item);
+
+            Ok(item.len())
+        })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<Tx, Rx, C> Encoder<crate::encoded::EncodedFrame<Tx, C>> for ByRef<Codec<Tx, Rx, C>> {
     type Error = EncodeError;
 
-    fn encode(&mut self, item: M, dst: &mut [u8]) -> Result<usize, Self::Error> {
-        if dst.len() < 4 {
+    fn encode(
+        &mut self,
+        item: crate::encoded::EncodedFrame<Tx, C>,
+        dst: &mut [u8],
+    ) -> Result<usize, Self::Error> {
+        if item.framing() != self.inner().framing() {
+            return Err(EncodeError::FramingMismatch);
+        }
+
+        let bytes = item.bytes();
+
+        if dst.len() < bytes.len() {
             return Err(EncodeError::InputBufferTooSmall);
         }
 
-        let message_size =
-            bincode::encode_into_slice(item, &mut dst[4..], bincode::config::standard())
-                .map_err(EncodeError::Encode)?;
+        dst[..bytes.len()].copy_from_slice(bytes);
+
+        Ok(bytes.len())
+    }
+}
+
+impl<E> From<frame::EncodeError> for EncodeError<E> {
+    fn from(err: frame::EncodeError) -> Self {
+        match err {
+            frame::EncodeError::MessageTooBig => EncodeError::MessageTooBig,
+            frame::EncodeError::FrameTooLarge { size, max } => {
+                EncodeError::FrameTooLarge { size, max }
+            }
+        }
+    }
+}
 
-        if message_size > u32::MAX as usize {
-            return Err(EncodeError::MessageTooBig);
+impl<E> fmt::Display for EncodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InputBufferTooSmall => write!(f, "buffer too small to encode a frame"),
+            EncodeError::Encode(_) => write!(f, "failed to encode message"),
+            EncodeError::MessageTooBig => write!(f, "message too big for the length prefix"),
+            EncodeError::FrameTooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+            EncodeError::FramingMismatch => {
+                write!(f, "frame encoded with a different framing than the codec's")
+            }
         }
+    }
+}
 
-        let packet_size = message_size as u32 + 4;
-        let packet_size_bytes = packet_size.to_be_bytes();
-        dst[0..4].copy_from_slice(&packet_size_bytes);
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for EncodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
-        Ok(packet_size as usize)
+impl<E> From<EncodeError<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: EncodeError<E>) -> Self {
+        match err {
+            EncodeError::InputBufferTooSmall => BridgeError::InputBufferTooSmall,
+            EncodeError::Encode(err) => err.into(),
+            EncodeError::MessageTooBig => BridgeError::MessageTooBig,
+            EncodeError::FrameTooLarge { size, max } => BridgeError::FrameTooLarge { size, max },
+            EncodeError::FramingMismatch => BridgeError::FramingMismatch,
+        }
     }
 }
 
 #[derive(Debug)]
-pub enum DecodeError {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError<E = bincode::error::DecodeError> {
     InvalidFrameSize,
-    Decode(bincode::error::DecodeError),
+    FrameTooLarge { announced: usize, max: usize },
+    FrameExceedsBuffer { announced: usize, capacity: usize },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
+}
+
+/// A frame whose payload could not be decoded. See [`Recovering`].
+///
+/// The frame is already consumed, so only its size is available. The [`Decoder`] of
+/// [`Recovering`] yields an [`InvalidFrame`] with the bytes instead.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidMessage<E = bincode::error::DecodeError> {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub error: E,
+    pub payload_len: usize,
 }
 
-impl<M> DecoderOwned for Codec<M>
+/// A frame whose payload could not be decoded, with the payload still in the read buffer.
+/// See [`Recovering`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidFrame<'buf, E = bincode::error::DecodeError> {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub error: E,
+    pub payload: &'buf [u8],
+}
+
+impl<E> fmt::Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            DecodeError::FrameTooLarge { announced, max } => write!(
+                f,
+                "announced frame of {announced} bytes exceeds the maximum of {max} bytes"
+            ),
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => write!(
+                f,
+                "frame of {announced} bytes exceeds the buffer of {capacity} bytes"
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            DecodeError::Decode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DecodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<DecodeError<E>> for BridgeError
 where
-    M: bincode::Decode,
+    BridgeError: From<E>,
 {
-    type Item = M;
+    fn from(err: DecodeError<E>) -> Self {
+        match err {
+            DecodeError::InvalidFrameSize => BridgeError::InvalidFrameSize,
+            DecodeError::FrameTooLarge { announced, max } => BridgeError::FrameTooLarge {
+                size: announced,
+                max,
+            },
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => BridgeError::FrameExceedsBuffer {
+                size: announced,
+                capacity,
+            },
+            DecodeError::ChecksumMismatch => BridgeError::ChecksumMismatch,
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
 
-    type Error = DecodeError;
+impl<E> fmt::Display for InvalidMessage<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to decode message of {} bytes", self.payload_len)
+    }
+}
 
-    fn decode_owned(&mut self, src: &mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
-        if src.len() < 4 {
-            return Ok(None);
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for InvalidMessage<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<E> From<InvalidMessage<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: InvalidMessage<E>) -> Self {
+        err.error.into()
+    }
+}
+
+impl<E> fmt::Display for InvalidFrame<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to decode message of {} bytes",
+            self.payload.len()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for InvalidFrame<'_, E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<E> From<InvalidFrame<'_, E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: InvalidFrame<'_, E>) -> Self {
+        err.error.into()
+    }
+}
+
+/// Finds the next complete frame in `src`, past any bytes skipped before it.
+///
+/// Returns the range of the payload in `src` and the number of bytes to consume.
+fn next_frame<Tx, Rx, C, E>(
+    codec: &mut Codec<Tx, Rx, C>,
+    src: &mut [u8],
+) -> Result<Option<(Range<usize>, usize)>, DecodeError<E>> {
+    // Skipped bytes can only be consumed together with the next frame.
+    // Until then they stay in the buffer and must not be looked at again.
+    let mut skipped = codec.skip_offset().min(src.len());
+
+    loop {
+        match codec.framing().decode_frame(&mut src[skipped..])? {
+            Decoded::Frame { payload, len } => {
+                codec.set_skip_offset(0);
+
+                let payload = skipped + payload.start..skipped + payload.end;
+
+                return Ok(Some((payload, skipped + len)));
+            }
+            Decoded::Skip { len, skipped: kind } => {
+                codec.record_skip(len, kind);
+
+                skipped += len;
+            }
+            Decoded::Incomplete { frame_len } => {
+                codec.set_skip_offset(skipped);
+
+                let announced = frame_len.unwrap_or(src.len() - skipped);
+
+                if skipped + announced > codec.buffer_capacity()
+                    || (frame_len.is_none() && src.len() >= codec.buffer_capacity())
+                {
+                    return Err(DecodeError::FrameExceedsBuffer {
+                        announced,
+                        capacity: codec.buffer_capacity(),
+                    });
+                }
+
+                return Ok(None);
+            }
         }
+    }
+}
+
+impl<Tx, Rx, C> DecoderOwned for Codec<Tx, Rx, C>
+where
+    C: Deserializer<Rx>,
+{
+    type Item = Rx;
+
+    type Error = DecodeError<C::Error>;
+
+    fn decode_owned(&mut self, src: &mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let item = self
+            .config()
+            .deserialize(&src[payload])
+            .map_err(DecodeError::Decode)?;
+
+        Ok(Some((item, len)))
+    }
+}
+
+impl<Tx, Rx, C> DecoderOwned for Recovering<Codec<Tx, Rx, C>>
+where
+    C: Deserializer<Rx>,
+{
+    type Item = Result<Rx, InvalidMessage<C::Error>>;
 
-        let frame_size = u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize;
+    type Error = DecodeError<C::Error>;
 
-        if src.len() < frame_size {
-            return Ok(None);
+    fn decode_owned(&mut self, src: &mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        let codec = self.inner_mut();
+
+        let (payload, len) = match next_frame(codec, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let payload_len = payload.len();
+
+        match codec.config().deserialize(&src[payload]) {
+            Ok(item) => Ok(Some((Ok(item), len))),
+            Err(error) => Ok(Some((Err(InvalidMessage { error, payload_len }), len))),
         }
+    }
+}
+
+impl<'buf, Tx, Rx, C> Decoder<'buf> for Codec<Tx, Rx, C>
+where
+    Rx: BorrowMessage,
+    C: bincode::config::Config,
+{
+    type Item = Rx::Message<'buf>;
+
+    type Error = DecodeError;
+
+    fn decode(&mut self, src: &'buf mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let src: &'buf [u8] = src;
+
+        let (item, _) = bincode::borrow_decode_from_slice(&src[payload], *self.config())
+            .map_err(DecodeError::Decode)?;
+
+        Ok(Some((item, len)))
+    }
+}
+
+impl<'buf, Tx, Rx, C> Decoder<'buf> for Recovering<Codec<Tx, Rx, C>>
+where
+    Rx: BorrowMessage,
+    C: bincode::config::Config,
+{
+    type Item = Result<Rx::Message<'buf>, InvalidFrame<'buf>>;
 
-        if frame_size < 4 {
-            return Err(DecodeError::InvalidFrameSize);
+    type Error = DecodeError;
+
+    fn decode(&mut self, src: &'buf mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        let codec = self.inner_mut();
+
+        let (payload, len) = match next_frame(codec, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let src: &'buf [u8] = src;
+        let payload = &src[payload];
+
+        match bincode::borrow_decode_from_slice(payload, *codec.config()) {
+            Ok((item, _)) => Ok(Some((Ok(item), len))),
+            Err(error) => Ok(Some((Err(InvalidFrame { error, payload }), len))),
         }
+    }
+}
+
+impl<'buf> Decoder<'buf> for Codec<RawFrame, RawFrame, Raw> {
+    type Item = &'buf [u8];
+
+    type Error = DecodeError<Infallible>;
+
+    fn decode(&mut self, src: &'buf mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let src: &'buf [u8] = src;
+
+        Ok(Some((&src[payload], len)))
+    }
+}
+
+impl<T, I> Encoder<I> for Recovering<T>
+where
+    T: Encoder<I>,
+{
+    type Error = T::Error;
+
+    fn encode(&mut self, item: I, dst: &mut [u8]) -> Result<usize, Self::Error> {
+        self.inner_mut().encode(item, dst)
+    }
+}
+
+impl<T> DecoderOwned for ByRef<T>
+where
+    T: DecoderOwned,
+{
+    type Item = T::Item;
+
+    type Error = T::Error;
+
+    fn decode_owned(&mut self, src: &mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        self.inner_mut().decode_owned(src)
+    }
+}
+
+impl<'buf, T> Decoder<'buf> for ByRef<T>
+where
+    T: Decoder<'buf>,
+{
+    type Item = T::Item;
+
+    type Error = T::Error;
 
-        let (item, _) =
-            bincode::decode_from_slice(&src[4..frame_size], bincode::config::standard())
-                .map_err(DecodeError::Decode)?;
+    fn decode(&mut self, src: &'buf mut [u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        self.inner_mut().decode(src)
+    }
+}
 
-        Ok(Some((item, frame_size)))
+impl<E> From<frame::DecodeError> for DecodeError<E> {
+    fn from(err: frame::DecodeError) -> Self {
+        match err {
+            frame::DecodeError::InvalidFrameSize => DecodeError::InvalidFrameSize,
+            frame::DecodeError::FrameTooLarge { announced, max } => {
+                DecodeError::FrameTooLarge { announced, max }
+            }
+            frame::DecodeError::ChecksumMismatch => DecodeError::ChecksumMismatch,
+        }
     }
 }
 
@@ -79,12 +550,15 @@ mod test {
     extern crate std;
     use std::vec::Vec;
 
-    use cody_c::{tokio::Compat, FramedRead, FramedWrite};
+    use cody_c::{tokio::Compat, Decoder, DecoderOwned, Encoder, FramedRead, FramedWrite};
     use futures::{pin_mut, SinkExt, StreamExt};
 
+    use super::{DecodeError, EncodeError, InvalidFrame, InvalidMessage};
     use crate::{
+        checksum::Checksum,
         codec::Codec,
-        test::{test_messages, TestMessage},
+        frame::{Framing, LengthMode, LengthPrefix},
+        test::{test_messages, z_test_message, BorrowedTestMessage, TestMessage},
     };
 
     #[tokio::test]
@@ -125,4 +599,374 @@ mod test {
 
         assert_eq!(collected_items, items);
     }
+
+    #[cfg(feature = "postcard")]
+    #[tokio::test]
+    async fn postcard_sink_stream() {
+        use crate::postcard::Postcard;
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec = Codec::<TestMessage, TestMessage, Postcard>::postcard();
+            let mut framed_write =
+                FramedWrite::new_with_buffer(codec, Compat::new(write), [0_u8; 128]);
+            let framed_write = framed_write.sink();
+
+            pin_mut!(framed_write);
+
+            for item in test_messages() {
+                framed_write.send(item).await.unwrap();
+            }
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage, TestMessage, Postcard>::postcard();
+        let mut framed_read = FramedRead::new_with_buffer(codec, Compat::new(read), [0_u8; 128]);
+        let framed_read = framed_read.stream();
+
+        let collected_items: Vec<_> = framed_read.map(Result::unwrap).collect().await;
+
+        handle.await.unwrap();
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_buffer_full() {
+        let mut codec = Codec::<TestMessage, TestMessage, _>::postcard();
+
+        let mut dst = [0_u8; 16];
+
+        let result = codec.encode(z_test_message(), &mut dst);
+
+        assert!(matches!(
+            result,
+            Err(EncodeError::Encode(postcard::Error::SerializeBufferFull))
+        ));
+    }
+
+    #[test]
+    fn decode_frame_too_large() {
+        let mut codec = Codec::<TestMessage>::new().with_max_frame_size(16);
+
+        let mut src = [0_u8; 8];
+        src[0..4].copy_from_slice(&32_u32.to_be_bytes());
+
+        let result = codec.decode_owned(&mut src);
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::FrameTooLarge {
+                announced: 32,
+                max: 16
+            })
+        ));
+    }
+
+    #[test]
+    fn encode_frame_too_large() {
+        let mut codec = Codec::<TestMessage>::new().with_max_frame_size(8);
+
+        let mut dst = [0_u8; 128];
+
+        let result = codec.encode(z_test_message(), &mut dst);
+
+        assert!(matches!(
+            result,
+            Err(EncodeError::FrameTooLarge { max: 8, .. })
+        ));
+    }
+
+    #[test]
+    fn decode_frame_exceeds_buffer() {
+        let mut codec = Codec::<TestMessage>::new().with_buffer_capacity(16);
+
+        let mut src = [0_u8; 16];
+        src[0..4].copy_from_slice(&32_u32.to_be_bytes());
+
+        let result = codec.decode_owned(&mut src);
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::FrameExceedsBuffer {
+                announced: 32,
+                capacity: 16
+            })
+        ));
+    }
+
+    #[test]
+    fn varint_payload_only_prefix() {
+        let mut codec = Codec::<TestMessage>::new().with_framing(
+            Framing::new()
+                .with_length_prefix(LengthPrefix::Varint)
+                .with_length_mode(LengthMode::PayloadOnly),
+        );
+
+        let mut buf = [0_u8; 128];
+
+        let size = codec.encode(z_test_message(), &mut buf).unwrap();
+
+        assert_eq!(buf[0] as usize, size - 1);
+
+        let (item, decoded_size) = codec.decode_owned(&mut buf[..size]).unwrap().unwrap();
+
+        assert_eq!(item, z_test_message());
+        assert_eq!(decoded_size, size);
+    }
+
+    #[test]
+    fn legacy_config() {
+        let mut codec =
+            Codec::<TestMessage, TestMessage, _>::with_config(bincode::config::legacy());
+
+        let mut buf = [0_u8; 128];
+
+        let size = codec.encode(TestMessage::A(100), &mut buf).unwrap();
+
+        assert_eq!(&buf[..size], &[0, 0, 0, 9, 0, 0, 0, 0, 100]);
+
+        let (item, decoded_size) = codec.decode_owned(&mut buf[..size]).unwrap().unwrap();
+
+        assert_eq!(item, TestMessage::A(100));
+        assert_eq!(decoded_size, size);
+    }
+
+    #[test]
+    fn crc16_trailer() {
+        let mut codec =
+            Codec::<TestMessage>::new().with_framing(Framing::new().with_checksum(Checksum::Crc16));
+
+        let mut buf = [0_u8; 128];
+
+        let size = codec.encode(z_test_message(), &mut buf).unwrap();
+
+        let (item, decoded_size) = codec.decode_owned(&mut buf[..size]).unwrap().unwrap();
+
+        assert_eq!(item, z_test_message());
+        assert_eq!(decoded_size, size);
+
+        // A flipped bit in the payload.
+        buf[10] ^= 0x04;
+
+        let result = codec.decode_owned(&mut buf[..size]);
+
+        assert!(matches!(result, Err(DecodeError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn cobs_drops_corrupted_frame() {
+        let mut codec = Codec::<TestMessage>::new()
+            .with_framing(Framing::cobs().with_checksum(Checksum::Crc16));
+
+        let mut buf = [0_u8; 256];
+
+        let first = codec.encode(TestMessage::A(100), &mut buf).unwrap();
+        let second = codec.encode(z_test_message(), &mut buf[first..]).unwrap();
+        let third = codec
+            .encode(TestMessage::B(100), &mut buf[first + second..])
+            .unwrap();
+
+        let total = first + second + third;
+
+        assert!(buf[..first - 1].iter().all(|&b| b != 0));
+        assert_eq!(buf[first - 1], 0);
+
+        buf[first + 5] ^= 0x10;
+
+        let (item, size) = codec.decode_owned(&mut buf[..total]).unwrap().unwrap();
+
+        assert_eq!(item, TestMessage::A(100));
+        assert_eq!(size, first);
+
+        // The decoder cannot consume the corrupted frame yet.
+        let result = codec.decode_owned(&mut buf[first..first + second]).unwrap();
+
+        assert!(result.is_none());
+        assert_eq!(codec.dropped_frames(), 1);
+
+        let (item, size) = codec.decode_owned(&mut buf[first..total]).unwrap().unwrap();
+
+        assert_eq!(item, TestMessage::B(100));
+        assert_eq!(size, second + third);
+        assert_eq!(codec.dropped_frames(), 1);
+    }
+
+    #[test]
+    fn skipped_bytes_count_against_buffer() {
+        let mut codec = Codec::<TestMessage>::new()
+            .with_framing(Framing::cobs().with_checksum(Checksum::Crc16))
+            .with_buffer_capacity(32);
+
+        let mut buf = [0x11_u8; 64];
+        buf[24] = 0;
+
+        codec.encode(z_test_message(), &mut buf[25..]).unwrap();
+
+        // A full read buffer of noise and the start of a frame.
+        let result = codec.decode_owned(&mut buf[..32]);
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::FrameExceedsBuffer { capacity: 32, .. })
+        ));
+        assert_eq!(codec.dropped_frames(), 1);
+    }
+
+    #[test]
+    fn recovering_skips_invalid_message() {
+        let mut codec = Codec::<TestMessage>::new().recovering();
+
+        let mut buf = [0_u8; 64];
+
+        let first = Codec::<u32>::new().encode(100, &mut buf).unwrap();
+        let second = codec.encode(TestMessage::H, &mut buf[first..]).unwrap();
+
+        let (item, size) = codec
+            .decode_owned(&mut buf[..first + second])
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(
+            item,
+            Err(InvalidMessage {
+                error: bincode::error::DecodeError::UnexpectedVariant { found: 100, .. },
+                payload_len: 1,
+            })
+        ));
+        assert_eq!(size, first);
+
+        let (item, size) = codec
+            .decode_owned(&mut buf[first..first + second])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(item.unwrap(), TestMessage::H);
+        assert_eq!(size, second);
+    }
+
+    #[test]
+    fn recovering_borrowed_yields_invalid_payload() {
+        let mut codec = Codec::<BorrowedTestMessage<'static>>::new().recovering();
+
+        let mut buf = [0_u8; 64];
+
+        let first = Codec::<u32>::new().encode(100, &mut buf).unwrap();
+        let second = codec
+            .encode(BorrowedTestMessage::Text("Hello"), &mut buf[first..])
+            .unwrap();
+
+        let (item, size) = Decoder::decode(&mut codec, &mut buf[..first + second])
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(
+            item,
+            Err(InvalidFrame {
+                error: bincode::error::DecodeError::UnexpectedVariant { found: 100, .. },
+                payload: [100],
+            })
+        ));
+        assert_eq!(size, first);
+
+        let (item, size) = Decoder::decode(&mut codec, &mut buf[first..first + second])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(item.unwrap(), BorrowedTestMessage::Text("Hello"));
+        assert_eq!(size, second);
+    }
+
+    #[test]
+    fn borrowed_decode() {
+        let mut codec = Codec::<BorrowedTestMessage<'static>>::new();
+
+        let mut buf = [0_u8; 64];
+
+        let size = codec
+            .encode(BorrowedTestMessage::Blob(7, &[1, 2, 3]), &mut buf)
+            .unwrap();
+
+        let buf_range = buf.as_ptr_range();
+
+        let (item, decoded_size) = Decoder::decode(&mut codec, &mut buf[..size])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(item, BorrowedTestMessage::Blob(7, &[1, 2, 3]));
+        assert_eq!(decoded_size, size);
+
+        // The blob points into the read buffer.
+        if let BorrowedTestMessage::Blob(_, blob) = item {
+            assert!(buf_range.contains(&blob.as_ptr()));
+        }
+    }
+
+    #[test]
+    fn encode_by_reference() {
+        let mut codec = Codec::<TestMessage>::new().by_ref();
+
+        let message = z_test_message();
+
+        let mut buf = [0_u8; 128];
+
+        let size = codec.encode(&message, &mut buf).unwrap();
+
+        let (item, decoded_size) = codec.decode_owned(&mut buf[..size]).unwrap().unwrap();
+
+        assert_eq!(item, message);
+        assert_eq!(decoded_size, size);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn encoded_frame() {
+        let mut codec = Codec::<TestMessage>::new().by_ref();
+
+        let frame = codec.inner().encode_frame(&z_test_message()).unwrap();
+
+        let mut buf = [0_u8; 128];
+
+        let size = codec.encode(frame.clone(), &mut buf).unwrap();
+
+        assert_eq!(&buf[..size], &frame.bytes()[..]);
+
+        let (item, _) = codec.decode_owned(&mut buf[..size]).unwrap().unwrap();
+
+        assert_eq!(item, z_test_message());
+
+        let result = codec.encode(frame, &mut buf[..8]);
+
+        assert!(matches!(result, Err(EncodeError::InputBufferTooSmall)));
+    }
+
+    #[test]
+    fn raw_frame_forwarding() {
+        let mut device = Codec::<TestMessage>::new();
+        let mut proxy = Codec::raw();
+
+        let mut inbound = [0_u8; 128];
+        let mut outbound = [0_u8; 128];
+
+        let size = device.encode(z_test_message(), &mut inbound).unwrap();
+        let frame = inbound;
+
+        let (payload, decoded_size) = Decoder::decode(&mut proxy, &mut inbound[..size])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(decoded_size, size);
+        assert_eq!(device.decode_payload(payload).unwrap(), z_test_message());
+
+        let forwarded_size = proxy.encode(payload, &mut outbound).unwrap();
+
+        assert_eq!(&outbound[..forwarded_size], &frame[..size]);
+
+        let result = proxy.encode(payload, &mut outbound[..8]);
+
+        assert!(matches!(result, Err(EncodeError::InputBufferTooSmall)));
+    }
 }
@@ -1,6 +1,100 @@
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode, PartialEq)]
-pub enum DemoMessage {
-    Ping(u32),
-    Pong(u32),
-    Measurement(i64),
+crate::max_encoded_size! {
+    /// Messages the server sends to the device.
+    #[derive(Debug, Clone, bincode::Encode, bincode::Decode, PartialEq)]
+    #[cfg_attr(feature = "cbor", derive(minicbor::Encode, minicbor::Decode))]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub enum ServerMessage {
+        #[cfg_attr(feature = "cbor", n(0))]
+        Ping(#[cfg_attr(feature = "cbor", n(0))] u32),
+    }
+}
+
+crate::max_encoded_size! {
+    /// Messages the device sends to the server.
+    #[derive(Debug, Clone, bincode::Encode, bincode::Decode, PartialEq)]
+    #[cfg_attr(feature = "cbor", derive(minicbor::Encode, minicbor::Decode))]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub enum DeviceMessage {
+        #[cfg_attr(feature = "cbor", n(0))]
+        Pong(#[cfg_attr(feature = "cbor", n(0))] u32),
+        #[cfg_attr(feature = "cbor", n(1))]
+        Measurement(#[cfg_attr(feature = "cbor", n(0))] i64),
+    }
+}
+
+#[allow(deprecated)]
+pub use legacy::DemoMessage;
+
+// A module of its own, so that the derives may use the deprecated type.
+#[allow(deprecated)]
+mod legacy {
+    /// Messages of both directions, before they were split.
+    ///
+    /// [`ServerMessage`](super::ServerMessage) and [`DeviceMessage`](super::DeviceMessage) number
+    /// their variants from zero, so on the wire `DeviceMessage::Pong` is `DemoMessage::Ping` and
+    /// `DeviceMessage::Measurement` is `DemoMessage::Pong`. Both ends of a link must switch at the same time.
+    #[deprecated(note = "use `ServerMessage` and `DeviceMessage`, which are not wire compatible")]
+    #[derive(Debug, Clone, bincode::Encode, bincode::Decode, PartialEq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub enum DemoMessage {
+        Ping(u32),
+        Pong(u32),
+        Measurement(i64),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeviceMessage, ServerMessage};
+    use crate::codec::Codec;
+
+    #[test]
+    fn wire_format() {
+        let mut dst = [0_u8; 16];
+
+        let server = Codec::<ServerMessage>::new();
+
+        let size = server
+            .encode_payload(&ServerMessage::Ping(7), &mut dst)
+            .unwrap();
+
+        assert_eq!(dst[..size], [0, 7]);
+        assert_eq!(
+            server.decode_payload(&dst[..size]).unwrap(),
+            ServerMessage::Ping(7)
+        );
+
+        let device = Codec::<DeviceMessage>::new();
+
+        for (message, bytes) in [
+            (DeviceMessage::Pong(7), &[0, 7][..]),
+            (DeviceMessage::Measurement(-2), &[1, 3][..]),
+        ] {
+            let size = device.encode_payload(&message, &mut dst).unwrap();
+
+            assert_eq!(&dst[..size], bytes);
+            assert_eq!(device.decode_payload(&dst[..size]).unwrap(), message);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn legacy_wire_format() {
+        use super::DemoMessage;
+
+        let codec = Codec::<DemoMessage>::new();
+
+        let mut dst = [0_u8; 16];
+
+        for (message, bytes) in [
+            (DemoMessage::Ping(7), &[0, 7][..]),
+            (DemoMessage::Pong(7), &[1, 7][..]),
+            (DemoMessage::Measurement(-2), &[2, 3][..]),
+        ] {
+            let size = codec.encode_payload(&message, &mut dst).unwrap();
+
+            assert_eq!(&dst[..size], bytes);
+            assert_eq!(codec.decode_payload(&dst[..size]).unwrap(), message);
+        }
+    }
 }
@@ -0,0 +1,557 @@
+//! Blocking framing over `embedded_io::Read` and `embedded_io::Write`, with caller-provided buffers.
+
+use core::fmt;
+
+use embedded_io::{Read, Write};
+
+use crate::{
+    codec::Codec,
+    error::BridgeError,
+    frame::{self, Decoded, Discard},
+    serializer::{Deserializer, Serializer, SliceWriter},
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum EncodeError<E, S = bincode::error::EncodeError> {
+    IO(E),
+    InputBufferTooSmall,
+    Encode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] S),
+    MessageTooBig,
+    FrameTooLarge { size: usize, max: usize },
+}
+
+impl<E, S> From<frame::EncodeError> for EncodeError<E, S> {
+    fn from(err: frame::EncodeError) -> Self {
+        match err {
+            frame::EncodeError::MessageTooBig => EncodeError::MessageTooBig,
+            frame::EncodeError::FrameTooLarge { size, max } => {
+                EncodeError::FrameTooLarge { size, max }
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug, S> fmt::Display for EncodeError<E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::IO(err) => write!(f, "I/O error: {err:?}"),
+            EncodeError::InputBufferTooSmall => write!(f, "buffer too small to encode a frame"),
+            EncodeError::Encode(_) => write!(f, "failed to encode message"),
+            EncodeError::MessageTooBig => write!(f, "message too big for the length prefix"),
+            EncodeError::FrameTooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E, S> std::error::Error for EncodeError<E, S>
+where
+    E: fmt::Debug,
+    S: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E, S> From<EncodeError<E, S>> for BridgeError
+where
+    E: embedded_io::Error,
+    BridgeError: From<S>,
+{
+    fn from(err: EncodeError<E, S>) -> Self {
+        match err {
+            EncodeError::IO(err) => BridgeError::EmbeddedIO(err.kind()),
+            EncodeError::InputBufferTooSmall => BridgeError::InputBufferTooSmall,
+            EncodeError::Encode(err) => err.into(),
+            EncodeError::MessageTooBig => BridgeError::MessageTooBig,
+            EncodeError::FrameTooLarge { size, max } => BridgeError::FrameTooLarge { size, max },
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError<E, S = bincode::error::DecodeError> {
+    IO(E),
+    UnexpectedEof,
+    InvalidFrameSize,
+    FrameTooLarge { announced: usize, max: usize },
+    FrameExceedsBuffer { announced: usize, capacity: usize },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] S),
+}
+
+impl<E, S> From<frame::DecodeError> for DecodeError<E, S> {
+    fn from(err: frame::DecodeError) -> Self {
+        match err {
+            frame::DecodeError::InvalidFrameSize => DecodeError::InvalidFrameSize,
+            frame::DecodeError::FrameTooLarge { announced, max } => {
+                DecodeError::FrameTooLarge { announced, max }
+            }
+            frame::DecodeError::ChecksumMismatch => DecodeError::ChecksumMismatch,
+        }
+    }
+}
+
+impl<E: fmt::Debug, S> fmt::Display for DecodeError<E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::IO(err) => write!(f, "I/O error: {err:?}"),
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of input within a frame"),
+            DecodeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            DecodeError::FrameTooLarge { announced, max } => write!(
+                f,
+                "announced frame of {announced} bytes exceeds the maximum of {max} bytes"
+            ),
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => write!(
+                f,
+                "frame of {announced} bytes exceeds the buffer of {capacity} bytes"
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            DecodeError::Decode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E, S> std::error::Error for DecodeError<E, S>
+where
+    E: fmt::Debug,
+    S: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E, S> From<DecodeError<E, S>> for BridgeError
+where
+    E: embedded_io::Error,
+    BridgeError: From<S>,
+{
+    fn from(err: DecodeError<E, S>) -> Self {
+        match err {
+            DecodeError::IO(err) => BridgeError::EmbeddedIO(err.kind()),
+            DecodeError::UnexpectedEof => BridgeError::UnexpectedEof,
+            DecodeError::InvalidFrameSize => BridgeError::InvalidFrameSize,
+            DecodeError::FrameTooLarge { announced, max } => BridgeError::FrameTooLarge {
+                size: announced,
+                max,
+            },
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => BridgeError::FrameExceedsBuffer {
+                size: announced,
+                capacity,
+            },
+            DecodeError::ChecksumMismatch => BridgeError::ChecksumMismatch,
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
+
+/// Reads messages from a blocking reader into `buffer`.
+///
+/// A frame must fit into `buffer` as a whole.
+pub struct FramedReader<'buf, R, T> {
+    reader: R,
+    codec: T,
+    buffer: &'buf mut [u8],
+    start: usize,
+    end: usize,
+    discard: Discard,
+}
+
+impl<'buf, R, T> FramedReader<'buf, R, T> {
+    #[inline]
+    pub fn new(reader: R, codec: T, buffer: &'buf mut [u8]) -> Self {
+        Self {
+            reader,
+            codec,
+            buffer,
+            start: 0,
+            end: 0,
+            discard: Discard::Nothing,
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+
+    #[inline]
+    fn consume(&mut self, len: usize) {
+        self.start += len;
+
+        if self.start == self.end {
+            self.clear();
+        }
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+}
+
+impl<R, Tx, Rx, C> FramedReader<'_, R, Codec<Tx, Rx, C>>
+where
+    R: Read,
+    C: Deserializer<Rx>,
+{
+    /// Blocks until the next message is read.
+    ///
+    /// Returns `Ok(None)` when the reader reaches the end of its input between two frames.
+    /// A frame whose payload fails to decode is consumed, so the next call reads the one after.
+    /// After a framing error, all buffered bytes are dropped. A frame that exceeds the buffer is
+    /// dropped together with its bytes that are read later.
+    pub fn recv(&mut self) -> Result<Option<Rx>, DecodeError<R::Error, C::Error>> {
+        loop {
+            let src = &mut self.buffer[self.start..self.end];
+
+            let decoded = match self.codec.framing().decode_frame(src) {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    self.clear();
+
+                    return Err(err.into());
+                }
+            };
+
+            match decoded {
+                Decoded::Frame { payload, len } => {
+                    let result = self.codec.config().deserialize(&src[payload]);
+
+                    self.consume(len);
+
+                    return result.map(Some).map_err(DecodeError::Decode);
+                }
+                Decoded::Skip { len, skipped } => {
+                    self.codec.record_skip(len, skipped);
+
+                    self.consume(len);
+                }
+                Decoded::Incomplete { frame_len } => {
+                    let capacity = self.buffer.len();
+                    let buffered = self.end - self.start;
+                    let announced = frame_len.unwrap_or(buffered);
+
+                    if announced > capacity || (frame_len.is_none() && buffered == capacity) {
+                        self.discard = Discard::rest(self.codec.framing(), frame_len, buffered);
+
+                        self.clear();
+
+                        return Err(DecodeError::FrameExceedsBuffer {
+                            announced,
+                            capacity,
+                        });
+                    }
+
+                    if self.start > 0 {
+                        self.buffer.copy_within(self.start..self.end, 0);
+                        self.start = 0;
+                        self.end = buffered;
+                    }
+
+                    let read = match self.reader.read(&mut self.buffer[self.end..]) {
+                        Ok(0) if buffered == 0 && self.discard == Discard::Nothing => {
+                            return Ok(None)
+                        }
+                        Ok(0) => return Err(DecodeError::UnexpectedEof),
+                        Ok(read) => self.end..self.end + read,
+                        Err(err) => return Err(DecodeError::IO(err)),
+                    };
+
+                    let discarded = self.discard.take(&self.buffer[read.clone()]);
+
+                    self.buffer
+                        .copy_within(read.start + discarded..read.end, read.start);
+                    self.end += read.len() - discarded;
+                }
+            }
+        }
+    }
+}
+
+/// Writes messages to a blocking writer, encoding them into `buffer` first.
+pub struct FramedWriter<'buf, W, T> {
+    writer: W,
+    codec: T,
+    buffer: &'buf mut [u8],
+}
+
+impl<'buf, W, T> FramedWriter<'buf, W, T> {
+    #[inline]
+    pub fn new(writer: W, codec: T, buffer: &'buf mut [u8]) -> Self {
+        Self {
+            writer,
+            codec,
+            buffer,
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+}
+
+impl<W, Tx, Rx, C> FramedWriter<'_, W, Codec<Tx, Rx, C>>
+where
+    W: Write,
+    C: Serializer<Tx>,
+{
+    /// Writes the frame of `item` and flushes the writer.
+    pub fn send(&mut self, item: &Tx) -> Result<(), EncodeError<W::Error, C::Error>> {
+        let framing = self.codec.framing();
+
+        let header_size = framing.max_header_len();
+        let reserved_size = framing.reserved_after(self.buffer.len());
+
+        if self.buffer.len() < header_size + reserved_size {
+            return Err(EncodeError::InputBufferTooSmall);
+        }
+
+        let payload_end = self.buffer.len() - reserved_size;
+
+        let mut writer = SliceWriter::new(&mut self.buffer[header_size..payload_end]);
+
+        self.codec
+            .config()
+            .serialize(item, &mut writer)
+            .map_err(EncodeError::Encode)?;
+
+        let message_size = writer.len();
+
+        let packet_size = framing.encode_frame(self.buffer, message_size)?;
+
+        self.writer
+            .write_all(&self.buffer[..packet_size])
+            .map_err(EncodeError::IO)?;
+        self.writer.flush().map_err(EncodeError::IO)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::vec::Vec;
+
+    use super::{DecodeError, FramedReader, FramedWriter};
+    use crate::{
+        checksum::Checksum,
+        codec::Codec,
+        frame::Framing,
+        test::{test_messages, z_test_message, TestMessage},
+    };
+
+    #[test]
+    fn send_recv() {
+        let framing = Framing::cobs().with_checksum(Checksum::Crc16);
+
+        let mut wire = [0_u8; 1024];
+        let mut write_buf = [0_u8; 128];
+
+        let mut writer = FramedWriter::new(
+            &mut wire[..],
+            Codec::<TestMessage>::new().with_framing(framing),
+            &mut write_buf,
+        );
+
+        for item in test_messages() {
+            writer.send(&item).unwrap();
+        }
+
+        let remaining = writer.into_inner().len();
+        let written = wire.len() - remaining;
+
+        let mut read_buf = [0_u8; 128];
+
+        let mut reader = FramedReader::new(
+            &wire[..written],
+            Codec::<TestMessage>::new().with_framing(framing),
+            &mut read_buf,
+        );
+
+        let mut collected_items = Vec::new();
+
+        while let Some(item) = reader.recv().unwrap() {
+            collected_items.push(item);
+        }
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn frame_exceeds_buffer() {
+        let mut wire = [0_u8; 128];
+        let mut write_buf = [0_u8; 128];
+
+        let mut writer =
+            FramedWriter::new(&mut wire[..], Codec::<TestMessage>::new(), &mut write_buf);
+
+        writer.send(&z_test_message()).unwrap();
+        writer.send(&TestMessage::H).unwrap();
+
+        let remaining = writer.into_inner().len();
+        let written = wire.len() - remaining;
+
+        let mut read_buf = [0_u8; 16];
+
+        let mut reader =
+            FramedReader::new(&wire[..written], Codec::<TestMessage>::new(), &mut read_buf);
+
+        assert!(matches!(
+            reader.recv(),
+            Err(DecodeError::FrameExceedsBuffer { capacity: 16, .. })
+        ));
+
+        // The rest of the oversized frame is skipped.
+        assert_eq!(reader.recv().unwrap(), Some(TestMessage::H));
+        assert_eq!(reader.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn recovers_after_framing_error() {
+        let framing = Framing::new().with_checksum(Checksum::Crc16);
+
+        let mut wire = [0_u8; 128];
+        let mut write_buf = [0_u8; 128];
+
+        let mut writer = FramedWriter::new(
+            &mut wire[..],
+            Codec::<TestMessage>::new().with_framing(framing),
+            &mut write_buf,
+        );
+
+        writer.send(&TestMessage::A(100)).unwrap();
+
+        let after_first = writer.get_ref().len();
+
+        writer.send(&TestMessage::H).unwrap();
+
+        let remaining = writer.into_inner().len();
+        let written = wire.len() - remaining;
+        let frame_len = wire.len() - after_first;
+
+        wire[4] ^= 0x01;
+
+        // Exactly the corrupted frame fits, so the next one is read by the next call.
+        let mut read_buf = [0_u8; 128];
+
+        let mut reader = FramedReader::new(
+            &wire[..written],
+            Codec::<TestMessage>::new().with_framing(framing),
+            &mut read_buf[..frame_len],
+        );
+
+        assert!(matches!(reader.recv(), Err(DecodeError::ChecksumMismatch)));
+        assert_eq!(reader.recv().unwrap(), Some(TestMessage::H));
+        assert_eq!(reader.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_message_is_consumed() {
+        let mut wire = [0_u8; 128];
+        let mut write_buf = [0_u8; 128];
+
+        let mut writer = FramedWriter::new(&mut wire[..], Codec::<u32>::new(), &mut write_buf);
+
+        writer.send(&100).unwrap();
+
+        let mut writer = FramedWriter::new(
+            writer.into_inner(),
+            Codec::<TestMessage>::new(),
+            &mut write_buf,
+        );
+
+        writer.send(&TestMessage::H).unwrap();
+
+        let remaining = writer.into_inner().len();
+        let written = wire.len() - remaining;
+
+        let mut read_buf = [0_u8; 16];
+
+        let mut reader =
+            FramedReader::new(&wire[..written], Codec::<TestMessage>::new(), &mut read_buf);
+
+        assert!(matches!(reader.recv(), Err(DecodeError::Decode(_))));
+        assert_eq!(reader.recv().unwrap(), Some(TestMessage::H));
+        assert_eq!(reader.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read_buf = [0_u8; 16];
+
+        let mut reader = FramedReader::new(
+            &[0, 0, 0, 5][..],
+            Codec::<TestMessage>::new(),
+            &mut read_buf,
+        );
+
+        assert!(matches!(reader.recv(), Err(DecodeError::UnexpectedEof)));
+    }
+}
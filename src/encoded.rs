@@ -0,0 +1,65 @@
+//! Messages encoded once and written to many connections.
+
+use core::marker::PhantomData;
+
+use bincode::config::Configuration;
+use tokio_util::bytes::Bytes;
+
+use crate::frame::Framing;
+
+/// A complete frame of a `Tx` message in the format `C`, created by `Codec::encode_frame`.
+///
+/// Writing it to a codec made with `Codec::by_ref` copies the bytes as they are, without
+/// serializing the message again. Cloning it only bumps a reference count.
+///
+/// Only codecs with the same format accept it, which is checked at compile time:
+///
+/// ```compile_fail
+/// use the_bridge::Codec;
+/// use tokio_util::{bytes::BytesMut, codec::Encoder};
+///
+/// let frame = Codec::<u32>::new().encode_frame(&7).unwrap();
+///
+/// let mut legacy = Codec::<u32, u32, _>::with_config(bincode::config::legacy()).by_ref();
+///
+/// legacy.encode(frame, &mut BytesMut::new()).unwrap();
+/// ```
+pub struct EncodedFrame<Tx, C = Configuration> {
+    bytes: Bytes,
+    framing: Framing,
+    _phantom: PhantomData<(Tx, C)>,
+}
+
+impl<Tx, C> EncodedFrame<Tx, C> {
+    #[inline]
+    pub(crate) const fn new(bytes: Bytes, framing: Framing) -> Self {
+        Self {
+            bytes,
+            framing,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The frame, including its header and trailer.
+    #[inline]
+    pub const fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+
+    /// The framing the frame was encoded with. Only codecs with the same framing accept it.
+    #[inline]
+    pub const fn framing(&self) -> &Framing {
+        &self.framing
+    }
+}
+
+impl<Tx, C> Clone for EncodedFrame<Tx, C> {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes.clone(), self.framing)
+    }
+}
@@ -0,0 +1,187 @@
+//! An error type for all backends.
+
+use core::fmt;
+
+/// Any encode or decode error of any backend.
+///
+/// Every backend error converts into it, so callers can handle them uniformly and use `?`
+/// across backends.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum BridgeError {
+    #[cfg(feature = "std")]
+    IO(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] std::io::Error),
+    #[cfg(feature = "embedded-io")]
+    EmbeddedIO(embedded_io::ErrorKind),
+    UnexpectedEof,
+    InputBufferTooSmall,
+    Encode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] bincode::error::EncodeError),
+    MessageTooBig,
+    FramingMismatch,
+    InvalidFrameSize,
+    /// A frame, sent or announced by a received header, is larger than the maximum frame size.
+    FrameTooLarge {
+        size: usize,
+        max: usize,
+    },
+    FrameExceedsBuffer {
+        size: usize,
+        capacity: usize,
+    },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] bincode::error::DecodeError),
+    #[cfg(feature = "postcard")]
+    Postcard(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] postcard::Error),
+    #[cfg(feature = "cbor")]
+    CborEncode(
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        minicbor::encode::Error<crate::serializer::BufferFull>,
+    ),
+    #[cfg(feature = "cbor")]
+    CborDecode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] minicbor::decode::Error),
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            BridgeError::IO(_) => write!(f, "I/O error"),
+            #[cfg(feature = "embedded-io")]
+            BridgeError::EmbeddedIO(kind) => write!(f, "I/O error: {kind:?}"),
+            BridgeError::UnexpectedEof => write!(f, "unexpected end of input within a frame"),
+            BridgeError::InputBufferTooSmall => write!(f, "buffer too small to encode a frame"),
+            BridgeError::Encode(_) => write!(f, "failed to encode message"),
+            BridgeError::MessageTooBig => write!(f, "message too big for the length prefix"),
+            BridgeError::FramingMismatch => {
+                write!(f, "frame encoded with a different framing than the codec's")
+            }
+            BridgeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            BridgeError::FrameTooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+            BridgeError::FrameExceedsBuffer { size, capacity } => write!(
+                f,
+                "frame of {size} bytes exceeds the buffer of {capacity} bytes"
+            ),
+            BridgeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            BridgeError::Decode(_) => write!(f, "failed to decode message"),
+            #[cfg(feature = "postcard")]
+            BridgeError::Postcard(_) => write!(f, "failed to encode or decode message"),
+            #[cfg(feature = "cbor")]
+            BridgeError::CborEncode(_) => write!(f, "failed to encode message"),
+            #[cfg(feature = "cbor")]
+            BridgeError::CborDecode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BridgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BridgeError::IO(err) => Some(err),
+            BridgeError::Encode(err) => Some(err),
+            BridgeError::Decode(err) => Some(err),
+            #[cfg(feature = "postcard")]
+            BridgeError::Postcard(err) => Some(err),
+            #[cfg(feature = "cbor")]
+            BridgeError::CborEncode(err) => Some(err),
+            #[cfg(feature = "cbor")]
+            BridgeError::CborDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<core::convert::Infallible> for BridgeError {
+    fn from(err: core::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl From<bincode::error::EncodeError> for BridgeError {
+    fn from(err: bincode::error::EncodeError) -> Self {
+        BridgeError::Encode(err)
+    }
+}
+
+impl From<bincode::error::DecodeError> for BridgeError {
+    fn from(err: bincode::error::DecodeError) -> Self {
+        BridgeError::Decode(err)
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl From<postcard::Error> for BridgeError {
+    fn from(err: postcard::Error) -> Self {
+        BridgeError::Postcard(err)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<minicbor::encode::Error<crate::serializer::BufferFull>> for BridgeError {
+    fn from(err: minicbor::encode::Error<crate::serializer::BufferFull>) -> Self {
+        BridgeError::CborEncode(err)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<minicbor::decode::Error> for BridgeError {
+    fn from(err: minicbor::decode::Error) -> Self {
+        BridgeError::CborDecode(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::{boxed::Box, error::Error, string::ToString};
+
+    use super::BridgeError;
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_errors_convert() {
+        use tokio_util::{bytes::BytesMut, codec::Decoder};
+
+        use crate::{codec::Codec, test::TestMessage};
+
+        fn decode(src: &mut BytesMut) -> Result<Option<TestMessage>, Box<dyn Error>> {
+            Ok(Codec::<TestMessage>::new().decode(src)?)
+        }
+
+        // A frame of 5 bytes with an unknown variant.
+        let err = decode(&mut BytesMut::from(&[0, 0, 0, 5, 100][..])).unwrap_err();
+
+        assert_eq!(err.to_string(), "failed to decode message");
+        assert!(err.source().is_some());
+
+        let err: crate::tokio::DecodeError = crate::tokio::DecodeError::FrameTooLarge {
+            announced: 100,
+            max: 10,
+        };
+        let err = BridgeError::from(err);
+
+        assert!(matches!(
+            err,
+            BridgeError::FrameTooLarge { size: 100, max: 10 }
+        ));
+    }
+
+    #[cfg(feature = "cody-c")]
+    #[test]
+    fn cody_errors_convert() {
+        fn bridge(err: crate::cody_c::EncodeError) -> Result<(), BridgeError> {
+            Err(err)?
+        }
+
+        let err = bridge(crate::cody_c::EncodeError::InputBufferTooSmall).unwrap_err();
+
+        assert!(matches!(err, BridgeError::InputBufferTooSmall));
+        assert_eq!(err.to_string(), "buffer too small to encode a frame");
+    }
+}
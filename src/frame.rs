@@ -0,0 +1,944 @@
+//! Wire format of a frame.
+
+#![cfg_attr(not(any(feature = "cody-c", feature = "tokio")), allow(dead_code))]
+
+use core::ops::Range;
+
+use crate::{checksum::Checksum, cobs};
+
+/// Width of the length prefix in front of every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LengthPrefix {
+    U8,
+    U16,
+    U32,
+    /// Unsigned LEB128, at most 5 bytes long.
+    Varint,
+}
+
+/// Byte order of fixed-width length prefixes. Varint prefixes are always little-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ByteOrder {
+    BigEndian,
+    LittleEndian,
+}
+
+/// What the value of the length prefix counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LengthMode {
+    /// The length counts the whole frame, including the prefix itself.
+    IncludesHeader,
+    /// The length counts the payload only, like `tokio_util::codec::LengthDelimitedCodec`.
+    PayloadOnly,
+}
+
+/// How the boundaries of a frame are found in the byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrameMode {
+    /// Every frame starts with a length prefix.
+    LengthPrefixed,
+    /// Every frame is COBS-encoded and terminated by a zero byte.
+    ///
+    /// A corrupted frame, or one over the maximum frame size, is dropped and decoding picks up
+    /// at the next zero byte.
+    /// Length prefix settings are ignored in this mode.
+    Cobs,
+}
+
+/// Describes how messages are framed on the wire.
+///
+/// The default is a 4-byte big-endian length that includes the header itself, without a checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Framing {
+    mode: FrameMode,
+    prefix: LengthPrefix,
+    byte_order: ByteOrder,
+    length_mode: LengthMode,
+    sync_word: &'static [u8],
+    checksum: Checksum,
+    max_frame_size: usize,
+}
+
+impl Framing {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            mode: FrameMode::LengthPrefixed,
+            prefix: LengthPrefix::U32,
+            byte_order: ByteOrder::BigEndian,
+            length_mode: LengthMode::IncludesHeader,
+            sync_word: &[],
+            checksum: Checksum::None,
+            max_frame_size: usize::MAX,
+        }
+    }
+
+    /// The default framing of `tokio_util::codec::LengthDelimitedCodec`:
+    /// a 4-byte big-endian length of the payload.
+    #[inline]
+    pub const fn length_delimited() -> Self {
+        Self::new().with_length_mode(LengthMode::PayloadOnly)
+    }
+
+    /// COBS-encoded frames terminated by a zero byte.
+    #[inline]
+    pub const fn cobs() -> Self {
+        Self::new().with_mode(FrameMode::Cobs)
+    }
+
+    #[inline]
+    pub const fn with_mode(self, mode: FrameMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    #[inline]
+    pub const fn with_length_prefix(self, prefix: LengthPrefix) -> Self {
+        Self { prefix, ..self }
+    }
+
+    #[inline]
+    pub const fn with_byte_order(self, byte_order: ByteOrder) -> Self {
+        Self { byte_order, ..self }
+    }
+
+    #[inline]
+    pub const fn with_length_mode(self, length_mode: LengthMode) -> Self {
+        Self {
+            length_mode,
+            ..self
+        }
+    }
+
+    /// Puts a magic sync word in front of every length prefix. It counts as part of the header.
+    ///
+    /// When the bytes at the start of a frame are not a valid header, the decoder scans forward
+    /// byte by byte to the next sync word followed by a valid length prefix, instead of failing.
+    /// A checksum and a maximum frame size make false matches within garbage unlikely.
+    /// Ignored in [`FrameMode::Cobs`].
+    #[inline]
+    pub const fn with_sync_word(self, sync_word: &'static [u8]) -> Self {
+        Self { sync_word, ..self }
+    }
+
+    /// Appends a checksum trailer to every frame, written in the configured byte order.
+    ///
+    /// In [`LengthMode::IncludesHeader`] the length prefix counts the trailer too.
+    #[inline]
+    pub const fn with_checksum(self, checksum: Checksum) -> Self {
+        Self { checksum, ..self }
+    }
+
+    /// Limits the size of a frame, including its header.
+    #[inline]
+    pub const fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub const fn mode(&self) -> FrameMode {
+        self.mode
+    }
+
+    #[inline]
+    pub const fn length_prefix(&self) -> LengthPrefix {
+        self.prefix
+    }
+
+    #[inline]
+    pub const fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    #[inline]
+    pub const fn length_mode(&self) -> LengthMode {
+        self.length_mode
+    }
+
+    #[inline]
+    pub const fn sync_word(&self) -> &'static [u8] {
+        self.sync_word
+    }
+
+    #[inline]
+    pub const fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    #[inline]
+    pub const fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Number of bytes an encoder reserves in front of the payload.
+    #[inline]
+    pub(crate) const fn max_header_len(&self) -> usize {
+        if let FrameMode::Cobs = self.mode {
+            return 0;
+        }
+
+        self.sync_word.len() + self.max_prefix_len()
+    }
+
+    #[inline]
+    const fn max_prefix_len(&self) -> usize {
+        match self.prefix {
+            LengthPrefix::U8 => 1,
+            LengthPrefix::U16 => 2,
+            LengthPrefix::U32 => 4,
+            LengthPrefix::Varint => VARINT_MAX_LEN,
+        }
+    }
+
+    #[inline]
+    const fn trailer_len(&self) -> usize {
+        self.checksum.size()
+    }
+
+    /// Number of bytes an encoder reserves behind a payload of `payload_len` bytes.
+    ///
+    /// The result never decreases as `payload_len` grows.
+    #[inline]
+    pub(crate) const fn reserved_after(&self, payload_len: usize) -> usize {
+        let trailer_len = self.trailer_len();
+
+        match self.mode {
+            FrameMode::LengthPrefixed => trailer_len,
+            FrameMode::Cobs => trailer_len + cobs::max_overhead(payload_len + trailer_len) + 1,
+        }
+    }
+
+    /// Largest frame of a payload of `payload_len` bytes, including its header and trailer.
+    #[inline]
+    pub(crate) const fn max_frame_len(&self, payload_len: usize) -> usize {
+        self.max_header_len() + payload_len + self.reserved_after(payload_len)
+    }
+
+    /// Looks for a complete frame at the start of `src`.
+    ///
+    /// COBS frames are decoded in place, so bytes reported as [`Decoded::Skip`]
+    /// must not be decoded again.
+    pub(crate) fn decode_frame(&self, src: &mut [u8]) -> Result<Decoded, DecodeError> {
+        match self.mode {
+            FrameMode::LengthPrefixed if !self.sync_word.is_empty() => {
+                self.decode_synchronized_frame(src)
+            }
+            FrameMode::LengthPrefixed => {
+                let header = match self.decode_header(src)? {
+                    Some(header) => header,
+                    None => return Ok(Decoded::Incomplete { frame_len: None }),
+                };
+
+                if src.len() < header.frame_len {
+                    return Ok(Decoded::Incomplete {
+                        frame_len: Some(header.frame_len),
+                    });
+                }
+
+                self.verify(&src[..header.frame_len])?;
+
+                Ok(Decoded::Frame {
+                    payload: header.payload(),
+                    len: header.frame_len,
+                })
+            }
+            FrameMode::Cobs => {
+                let end = match src.iter().position(|&byte| byte == 0) {
+                    Some(end) => end,
+                    // No frame this long is valid, so its bytes are dropped up to the next zero.
+                    None if src.len() > self.max_frame_size => {
+                        return Ok(Decoded::Skip {
+                            len: src.len(),
+                            skipped: Skipped::Garbage,
+                        });
+                    }
+                    None => return Ok(Decoded::Incomplete { frame_len: None }),
+                };
+
+                let len = end + 1;
+
+                if end == 0 {
+                    return Ok(Decoded::Skip {
+                        len,
+                        skipped: Skipped::Padding,
+                    });
+                }
+
+                if len > self.max_frame_size {
+                    return Ok(Decoded::Skip {
+                        len,
+                        skipped: Skipped::Frame,
+                    });
+                }
+
+                let data_len = match cobs::decode_in_place(&mut src[..end]) {
+                    Some(data_len) if data_len >= self.trailer_len() => data_len,
+                    _ => {
+                        return Ok(Decoded::Skip {
+                            len,
+                            skipped: Skipped::Frame,
+                        });
+                    }
+                };
+
+                if self.verify(&src[..data_len]).is_err() {
+                    return Ok(Decoded::Skip {
+                        len,
+                        skipped: Skipped::Frame,
+                    });
+                }
+
+                Ok(Decoded::Frame {
+                    payload: 0..data_len - self.trailer_len(),
+                    len,
+                })
+            }
+        }
+    }
+
+    fn decode_synchronized_frame(&self, src: &[u8]) -> Result<Decoded, DecodeError> {
+        let sync_word = self.sync_word;
+
+        // A sync word, or the start of one at the end of `src`.
+        let start = (0..src.len())
+            .find(|&i| {
+                let len = (src.len() - i).min(sync_word.len());
+
+                src[i..i + len] == sync_word[..len]
+            })
+            .unwrap_or(src.len());
+
+        if start > 0 {
+            return Ok(Decoded::Skip {
+                len: start,
+                skipped: Skipped::Garbage,
+            });
+        }
+
+        let header = match self.decode_header(src) {
+            Ok(Some(header)) => header,
+            Ok(None) => return Ok(Decoded::Incomplete { frame_len: None }),
+            Err(_) => {
+                return Ok(Decoded::Skip {
+                    len: 1,
+                    skipped: Skipped::Garbage,
+                })
+            }
+        };
+
+        if src.len() < header.frame_len {
+            return Ok(Decoded::Incomplete {
+                frame_len: Some(header.frame_len),
+            });
+        }
+
+        if self.verify(&src[..header.frame_len]).is_err() {
+            return Ok(Decoded::Skip {
+                len: 1,
+                skipped: Skipped::Garbage,
+            });
+        }
+
+        Ok(Decoded::Frame {
+            payload: header.payload(),
+            len: header.frame_len,
+        })
+    }
+
+    /// Reads the header at the start of `src`.
+    ///
+    /// Returns `Ok(None)` if `src` does not hold a complete header yet.
+    fn decode_header(&self, src: &[u8]) -> Result<Option<Header>, DecodeError> {
+        let sync_len = self.sync_word.len();
+
+        let src = match src.get(sync_len..) {
+            Some(src) => src,
+            None => return Ok(None),
+        };
+
+        let (value, prefix_len) = match self.prefix {
+            LengthPrefix::U8 => match src.first() {
+                Some(&byte) => (byte as u64, 1),
+                None => return Ok(None),
+            },
+            LengthPrefix::U16 => match src.get(..2) {
+                Some(bytes) => {
+                    let bytes = [bytes[0], bytes[1]];
+
+                    let value = match self.byte_order {
+                        ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+                        ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+                    };
+
+                    (value as u64, 2)
+                }
+                None => return Ok(None),
+            },
+            LengthPrefix::U32 => match src.get(..4) {
+                Some(bytes) => {
+                    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+                    let value = match self.byte_order {
+                        ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+                        ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+                    };
+
+                    (value as u64, 4)
+                }
+                None => return Ok(None),
+            },
+            LengthPrefix::Varint => match decode_varint(src)? {
+                Some(decoded) => decoded,
+                None => return Ok(None),
+            },
+        };
+
+        let header_len = sync_len + prefix_len;
+        let trailer_len = self.trailer_len();
+        let overhead = (header_len + trailer_len) as u64;
+
+        let frame_len = match self.length_mode {
+            LengthMode::IncludesHeader => {
+                if value < overhead {
+                    return Err(DecodeError::InvalidFrameSize);
+                }
+
+                value
+            }
+            LengthMode::PayloadOnly => value + overhead,
+        };
+
+        if frame_len > self.max_frame_size as u64 {
+            return Err(DecodeError::FrameTooLarge {
+                announced: usize::try_from(frame_len).unwrap_or(usize::MAX),
+                max: self.max_frame_size,
+            });
+        }
+
+        Ok(Some(Header {
+            header_len,
+            trailer_len,
+            frame_len: frame_len as usize,
+        }))
+    }
+
+    /// Checks the trailer of a complete frame.
+    fn verify(&self, frame: &[u8]) -> Result<(), DecodeError> {
+        if self.checksum == Checksum::None {
+            return Ok(());
+        }
+
+        let (data, trailer) = frame.split_at(frame.len() - self.trailer_len());
+
+        if self.checksum.compute(data) != self.read_trailer(trailer) {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Fails like [`Self::encode_frame`] for a payload of `payload_len` bytes, before any of it
+    /// is written.
+    ///
+    /// COBS frames are checked with their smallest possible size.
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub(crate) fn check_payload_len(&self, payload_len: usize) -> Result<(), EncodeError> {
+        let frame_len = match self.mode {
+            FrameMode::LengthPrefixed => {
+                let (header_len, _) = self.header_for(payload_len)?;
+
+                header_len + payload_len + self.trailer_len()
+            }
+            // At least one byte of COBS overhead and the delimiter.
+            FrameMode::Cobs => payload_len + self.trailer_len() + 2,
+        };
+
+        if frame_len > self.max_frame_size {
+            return Err(EncodeError::FrameTooLarge {
+                size: frame_len,
+                max: self.max_frame_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Turns a payload into a frame at the start of `buf`.
+    ///
+    /// The payload must have been written to `buf[self.max_header_len()..]`, followed by
+    /// at least [`Self::reserved_after`] bytes of room.
+    /// If the header is shorter than reserved, the payload is moved up to follow it.
+    ///
+    /// Returns the length of the frame.
+    pub(crate) fn encode_frame(
+        &self,
+        buf: &mut [u8],
+        payload_len: usize,
+    ) -> Result<usize, EncodeError> {
+        if let FrameMode::Cobs = self.mode {
+            return self.encode_cobs_frame(buf, payload_len);
+        }
+
+        let (header_len, value) = self.header_for(payload_len)?;
+        let data_len = header_len + payload_len;
+        let frame_len = data_len + self.trailer_len();
+
+        if frame_len > self.max_frame_size {
+            return Err(EncodeError::FrameTooLarge {
+                size: frame_len,
+                max: self.max_frame_size,
+            });
+        }
+
+        let reserved = self.max_header_len();
+        if header_len < reserved {
+            buf.copy_within(reserved..reserved + payload_len, header_len);
+        }
+
+        let sync_len = self.sync_word.len();
+        buf[..sync_len].copy_from_slice(self.sync_word);
+
+        let prefix = &mut buf[sync_len..header_len];
+
+        match self.prefix {
+            LengthPrefix::U8 => prefix[0] = value as u8,
+            LengthPrefix::U16 => {
+                let bytes = match self.byte_order {
+                    ByteOrder::BigEndian => (value as u16).to_be_bytes(),
+                    ByteOrder::LittleEndian => (value as u16).to_le_bytes(),
+                };
+
+                prefix.copy_from_slice(&bytes);
+            }
+            LengthPrefix::U32 => {
+                let bytes = match self.byte_order {
+                    ByteOrder::BigEndian => value.to_be_bytes(),
+                    ByteOrder::LittleEndian => value.to_le_bytes(),
+                };
+
+                prefix.copy_from_slice(&bytes);
+            }
+            LengthPrefix::Varint => encode_varint(value, prefix),
+        }
+
+        if self.checksum != Checksum::None {
+            let checksum = self.checksum.compute(&buf[..data_len]);
+
+            self.write_trailer(checksum, &mut buf[data_len..frame_len]);
+        }
+
+        Ok(frame_len)
+    }
+
+    fn encode_cobs_frame(&self, buf: &mut [u8], payload_len: usize) -> Result<usize, EncodeError> {
+        let data_len = payload_len + self.trailer_len();
+
+        if self.checksum != Checksum::None {
+            let checksum = self.checksum.compute(&buf[..payload_len]);
+
+            self.write_trailer(checksum, &mut buf[payload_len..data_len]);
+        }
+
+        let encoded_len = cobs::encode_in_place(buf, data_len);
+        let frame_len = encoded_len + 1;
+
+        if frame_len > self.max_frame_size {
+            return Err(EncodeError::FrameTooLarge {
+                size: frame_len,
+                max: self.max_frame_size,
+            });
+        }
+
+        buf[encoded_len] = 0;
+
+        Ok(frame_len)
+    }
+
+    fn read_trailer(&self, trailer: &[u8]) -> u32 {
+        match (trailer, self.byte_order) {
+            (&[a, b], ByteOrder::BigEndian) => u16::from_be_bytes([a, b]) as u32,
+            (&[a, b], ByteOrder::LittleEndian) => u16::from_le_bytes([a, b]) as u32,
+            (&[a, b, c, d], ByteOrder::BigEndian) => u32::from_be_bytes([a, b, c, d]),
+            (&[a, b, c, d], ByteOrder::LittleEndian) => u32::from_le_bytes([a, b, c, d]),
+            _ => 0,
+        }
+    }
+
+    fn write_trailer(&self, checksum: u32, trailer: &mut [u8]) {
+        match (self.checksum, self.byte_order) {
+            (Checksum::None, _) => {}
+            (Checksum::Crc16, ByteOrder::BigEndian) => {
+                trailer.copy_from_slice(&(checksum as u16).to_be_bytes())
+            }
+            (Checksum::Crc16, ByteOrder::LittleEndian) => {
+                trailer.copy_from_slice(&(checksum as u16).to_le_bytes())
+            }
+            (Checksum::Crc32, ByteOrder::BigEndian) => {
+                trailer.copy_from_slice(&checksum.to_be_bytes())
+            }
+            (Checksum::Crc32, ByteOrder::LittleEndian) => {
+                trailer.copy_from_slice(&checksum.to_le_bytes())
+            }
+        }
+    }
+
+    /// Returns the header length and the value of the length prefix for a payload.
+    fn header_for(&self, payload_len: usize) -> Result<(usize, u32), EncodeError> {
+        let max_value = match self.prefix {
+            LengthPrefix::U8 => u8::MAX as u64,
+            LengthPrefix::U16 => u16::MAX as u64,
+            LengthPrefix::U32 | LengthPrefix::Varint => u32::MAX as u64,
+        };
+
+        let sync_len = self.sync_word.len();
+        let trailer_len = self.trailer_len();
+
+        let prefix_len = match self.prefix {
+            LengthPrefix::Varint => match self.length_mode {
+                LengthMode::PayloadOnly => varint_len(payload_len as u64),
+                // The prefix counts itself, so its length depends on its own value.
+                LengthMode::IncludesHeader => (1..VARINT_MAX_LEN)
+                    .find(|&len| {
+                        varint_len((sync_len + payload_len + len + trailer_len) as u64) == len
+                    })
+                    .unwrap_or(VARINT_MAX_LEN),
+            },
+            _ => self.max_prefix_len(),
+        };
+
+        let header_len = sync_len + prefix_len;
+
+        let value = match self.length_mode {
+            LengthMode::IncludesHeader => (payload_len + header_len + trailer_len) as u64,
+            LengthMode::PayloadOnly => payload_len as u64,
+        };
+
+        if value > max_value {
+            return Err(EncodeError::MessageTooBig);
+        }
+
+        Ok((header_len, value as u32))
+    }
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of looking for a frame at the start of a buffer.
+#[derive(Debug)]
+pub(crate) enum Decoded {
+    /// A complete frame of `len` bytes with its payload at `payload`.
+    Frame { payload: Range<usize>, len: usize },
+    /// `len` bytes that do not hold a message and must be discarded.
+    Skip { len: usize, skipped: Skipped },
+    /// More bytes are needed. `frame_len` is the length of the frame, if it is already known.
+    Incomplete { frame_len: Option<usize> },
+}
+
+/// What a decoder discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Skipped {
+    /// Delimiters between frames.
+    Padding,
+    /// A corrupted frame.
+    Frame,
+    /// Bytes that do not start a valid frame.
+    Garbage,
+}
+
+/// The rest of a frame too large for a read buffer, dropped as it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Discard {
+    Nothing,
+    Bytes(usize),
+    /// Up to and including the zero that ends a COBS frame.
+    UntilDelimiter,
+}
+
+impl Discard {
+    /// The rest of a frame of `frame_len` bytes, if known, after its first `buffered` bytes.
+    pub(crate) fn rest(framing: &Framing, frame_len: Option<usize>, buffered: usize) -> Self {
+        match frame_len {
+            Some(frame_len) => Discard::Bytes(frame_len - buffered),
+            None if framing.mode() == FrameMode::Cobs => Discard::UntilDelimiter,
+            None => Discard::Nothing,
+        }
+    }
+
+    /// Returns how many bytes at the start of `data` still belong to the frame.
+    pub(crate) fn take(&mut self, data: &[u8]) -> usize {
+        match *self {
+            Discard::Nothing => 0,
+            Discard::Bytes(remaining) if remaining > data.len() => {
+                *self = Discard::Bytes(remaining - data.len());
+
+                data.len()
+            }
+            Discard::Bytes(remaining) => {
+                *self = Discard::Nothing;
+
+                remaining
+            }
+            Discard::UntilDelimiter => match data.iter().position(|&byte| byte == 0) {
+                Some(end) => {
+                    *self = Discard::Nothing;
+
+                    end + 1
+                }
+                None => data.len(),
+            },
+        }
+    }
+}
+
+/// The header of a frame, as announced by its length prefix.
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    header_len: usize,
+    trailer_len: usize,
+    frame_len: usize,
+}
+
+impl Header {
+    /// Position of the payload within the frame.
+    #[inline]
+    fn payload(&self) -> Range<usize> {
+        self.header_len..self.frame_len - self.trailer_len
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum EncodeError {
+    MessageTooBig,
+    FrameTooLarge { size: usize, max: usize },
+}
+
+#[derive(Debug)]
+pub(crate) enum DecodeError {
+    InvalidFrameSize,
+    FrameTooLarge { announced: usize, max: usize },
+    ChecksumMismatch,
+}
+
+const VARINT_MAX_LEN: usize = 5;
+
+fn varint_len(value: u64) -> usize {
+    let mut len = 1;
+    let mut value = value >> 7;
+
+    while value != 0 {
+        len += 1;
+        value >>= 7;
+    }
+
+    len
+}
+
+fn encode_varint(value: u32, dst: &mut [u8]) {
+    let mut value = value;
+
+    for byte in dst.iter_mut() {
+        *byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value != 0 {
+            *byte |= 0x80;
+        }
+    }
+}
+
+fn decode_varint(src: &[u8]) -> Result<Option<(u64, usize)>, DecodeError> {
+    let mut value = 0_u64;
+
+    for (i, &byte) in src.iter().take(VARINT_MAX_LEN).enumerate() {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            if value > u32::MAX as u64 {
+                return Err(DecodeError::InvalidFrameSize);
+            }
+
+            return Ok(Some((value, i + 1)));
+        }
+    }
+
+    if src.len() >= VARINT_MAX_LEN {
+        return Err(DecodeError::InvalidFrameSize);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(framing: Framing, payload_len: usize) {
+        let mut buf = [0xAA_u8; 512];
+
+        let frame_len = framing.encode_frame(&mut buf, payload_len).unwrap();
+        let header = framing.decode_header(&buf).unwrap().unwrap();
+
+        assert_eq!(header.frame_len, frame_len);
+        assert_eq!(header.payload().len(), payload_len);
+        assert!(buf[header.payload()].iter().all(|&b| b == 0xAA));
+        assert!(framing.verify(&buf[..frame_len]).is_ok());
+    }
+
+    #[test]
+    fn header_round_trip() {
+        for prefix in [
+            LengthPrefix::U8,
+            LengthPrefix::U16,
+            LengthPrefix::U32,
+            LengthPrefix::Varint,
+        ] {
+            for byte_order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+                for length_mode in [LengthMode::IncludesHeader, LengthMode::PayloadOnly] {
+                    for checksum in [Checksum::None, Checksum::Crc16, Checksum::Crc32] {
+                        for sync_word in [&b""[..], &b"\xA5\x5A"[..]] {
+                            let framing = Framing::new()
+                                .with_length_prefix(prefix)
+                                .with_byte_order(byte_order)
+                                .with_length_mode(length_mode)
+                                .with_sync_word(sync_word)
+                                .with_checksum(checksum);
+
+                            for payload_len in
+                                [0, 1, 121, 122, 123, 124, 125, 126, 127, 128, 200, 248]
+                            {
+                                round_trip(framing, payload_len);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn varint_includes_itself() {
+        let framing = Framing::new().with_length_prefix(LengthPrefix::Varint);
+
+        let mut buf = [0_u8; 512];
+
+        // 126 + 1 fits into one byte, 127 + 1 does not.
+        assert_eq!(framing.encode_frame(&mut buf, 126).unwrap(), 127);
+        assert_eq!(buf[0], 127);
+        assert_eq!(framing.encode_frame(&mut buf, 127).unwrap(), 129);
+        assert_eq!(&buf[..2], &[0x81, 0x01]);
+    }
+
+    #[test]
+    fn prefix_too_small() {
+        let framing = Framing::new().with_length_prefix(LengthPrefix::U8);
+
+        let mut buf = [0_u8; 512];
+
+        assert!(matches!(
+            framing.encode_frame(&mut buf, 255),
+            Err(EncodeError::MessageTooBig)
+        ));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let framing = Framing::new().with_length_prefix(LengthPrefix::U16);
+
+        assert!(matches!(
+            framing.decode_header(&[0, 1]),
+            Err(DecodeError::InvalidFrameSize)
+        ));
+        assert!(matches!(framing.decode_header(&[0]), Ok(None)));
+
+        let framing = Framing::new().with_length_prefix(LengthPrefix::Varint);
+
+        assert!(matches!(
+            framing.decode_header(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Err(DecodeError::InvalidFrameSize)
+        ));
+        assert!(matches!(framing.decode_header(&[0xFF, 0xFF]), Ok(None)));
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let framing = Framing::new().with_checksum(Checksum::Crc32);
+
+        let mut buf = [0_u8; 16];
+        buf[4..8].copy_from_slice(b"ping");
+
+        let frame_len = framing.encode_frame(&mut buf, 4).unwrap();
+
+        assert_eq!(frame_len, 12);
+        assert!(framing.verify(&buf[..frame_len]).is_ok());
+
+        buf[5] ^= 0x01;
+
+        assert!(matches!(
+            framing.verify(&buf[..frame_len]),
+            Err(DecodeError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn sync_word_scan() {
+        let framing = Framing::new()
+            .with_length_prefix(LengthPrefix::U8)
+            .with_sync_word(b"\xA5\x5A");
+
+        let mut buf = [0_u8; 16];
+        buf[3..5].copy_from_slice(b"ok");
+
+        let frame_len = framing.encode_frame(&mut buf, 2).unwrap();
+
+        assert_eq!(&buf[..frame_len], b"\xA5\x5A\x05ok");
+
+        // Garbage up to the sync word is skipped at once.
+        let mut src = [0x11, 0xA5, 0x22, 0xA5, 0x5A, 0x05, b'o', b'k'];
+
+        assert!(matches!(
+            framing.decode_frame(&mut src),
+            Ok(Decoded::Skip {
+                len: 3,
+                skipped: Skipped::Garbage
+            })
+        ));
+        assert!(matches!(
+            framing.decode_frame(&mut src[3..]),
+            Ok(Decoded::Frame { len: 5, .. })
+        ));
+
+        // A partial sync word at the end is kept.
+        let mut src = [0x11, 0x22, 0xA5];
+
+        assert!(matches!(
+            framing.decode_frame(&mut src),
+            Ok(Decoded::Skip { len: 2, .. })
+        ));
+        assert!(matches!(
+            framing.decode_frame(&mut src[2..]),
+            Ok(Decoded::Incomplete { frame_len: None })
+        ));
+
+        // A sync word followed by an invalid header is skipped byte by byte.
+        let mut src = [0xA5, 0x5A, 0x00, 0xA5, 0x5A];
+
+        assert!(matches!(
+            framing.decode_frame(&mut src),
+            Ok(Decoded::Skip {
+                len: 1,
+                skipped: Skipped::Garbage
+            })
+        ));
+    }
+}
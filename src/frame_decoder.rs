@@ -0,0 +1,423 @@
+//! Decoding without an I/O framework, for bytes pushed from interrupts or callbacks.
+
+use core::fmt;
+
+use crate::{
+    codec::Codec,
+    error::BridgeError,
+    frame::{Decoded, Discard},
+    serializer::Deserializer,
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError<E = bincode::error::DecodeError> {
+    InvalidFrameSize,
+    FrameTooLarge { announced: usize, max: usize },
+    FrameExceedsBuffer { announced: usize, capacity: usize },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
+}
+
+impl<E> From<crate::frame::DecodeError> for DecodeError<E> {
+    fn from(err: crate::frame::DecodeError) -> Self {
+        match err {
+            crate::frame::DecodeError::InvalidFrameSize => DecodeError::InvalidFrameSize,
+            crate::frame::DecodeError::FrameTooLarge { announced, max } => {
+                DecodeError::FrameTooLarge { announced, max }
+            }
+            crate::frame::DecodeError::ChecksumMismatch => DecodeError::ChecksumMismatch,
+        }
+    }
+}
+
+impl<E> fmt::Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            DecodeError::FrameTooLarge { announced, max } => write!(
+                f,
+                "announced frame of {announced} bytes exceeds the maximum of {max} bytes"
+            ),
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => write!(
+                f,
+                "frame of {announced} bytes exceeds the buffer of {capacity} bytes"
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            DecodeError::Decode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DecodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<DecodeError<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: DecodeError<E>) -> Self {
+        match err {
+            DecodeError::InvalidFrameSize => BridgeError::InvalidFrameSize,
+            DecodeError::FrameTooLarge { announced, max } => BridgeError::FrameTooLarge {
+                size: announced,
+                max,
+            },
+            DecodeError::FrameExceedsBuffer {
+                announced,
+                capacity,
+            } => BridgeError::FrameExceedsBuffer {
+                size: announced,
+                capacity,
+            },
+            DecodeError::ChecksumMismatch => BridgeError::ChecksumMismatch,
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
+
+/// Decodes messages from bytes fed to it, in a fixed buffer of `N` bytes.
+///
+/// ```
+/// use the_bridge::{Codec, FrameDecoder};
+///
+/// let mut decoder = FrameDecoder::<_, 64>::new(Codec::<u32>::new());
+///
+/// // From a UART interrupt, one byte at a time.
+/// for byte in [0, 0, 0, 5, 42] {
+///     decoder.feed(&[byte]);
+/// }
+///
+/// let messages: Vec<u32> = decoder.messages().map(Result::unwrap).collect();
+///
+/// assert_eq!(messages, [42]);
+/// ```
+pub struct FrameDecoder<T, const N: usize> {
+    codec: T,
+    buffer: [u8; N],
+    start: usize,
+    end: usize,
+    discard: Discard,
+}
+
+impl<T, const N: usize> FrameDecoder<T, N> {
+    #[inline]
+    pub const fn new(codec: T) -> Self {
+        Self {
+            codec,
+            buffer: [0; N],
+            start: 0,
+            end: 0,
+            discard: Discard::Nothing,
+        }
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+
+    #[inline]
+    pub fn into_codec(self) -> T {
+        self.codec
+    }
+
+    /// Number of bytes fed but not decoded yet.
+    #[inline]
+    pub const fn buffered(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Copies as many bytes of `data` into the buffer as fit and returns their number.
+    ///
+    /// Decoded bytes are dropped from the buffer first if `data` does not fit behind the rest.
+    /// The rest of a frame that exceeded the buffer is dropped instead of copied, and counts as
+    /// fed.
+    pub fn feed(&mut self, data: &[u8]) -> usize {
+        let discarded = self.discard.take(data);
+        let data = &data[discarded..];
+
+        if N - self.end < data.len() && self.start > 0 {
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        let len = data.len().min(N - self.end);
+
+        self.buffer[self.end..self.end + len].copy_from_slice(&data[..len]);
+        self.end += len;
+
+        discarded + len
+    }
+
+    /// Drops all buffered bytes.
+    ///
+    /// The rest of a frame that exceeded the buffer is still dropped when it is fed.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+
+    #[inline]
+    fn consume(&mut self, len: usize) {
+        self.start += len;
+
+        if self.start == self.end {
+            self.clear();
+        }
+    }
+}
+
+/// Formats the codec and how full the buffer is.
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format, const N: usize> defmt::Format for FrameDecoder<T, N> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "FrameDecoder {{ codec: {}, buffered: {=usize}/{=usize} }}",
+            self.codec,
+            self.buffered(),
+            N,
+        )
+    }
+}
+
+impl<Tx, Rx, C, const N: usize> FrameDecoder<Codec<Tx, Rx, C>, N>
+where
+    C: Deserializer<Rx>,
+{
+    /// Decodes the next message from the buffered bytes.
+    ///
+    /// Returns `Ok(None)` until a complete frame has been fed. A frame that fails to decode is
+    /// consumed, so decoding can go on with the next one. After a framing error, all buffered
+    /// bytes are dropped. A frame that exceeds the buffer is dropped together with its bytes that
+    /// are fed later.
+    pub fn decode(&mut self) -> Result<Option<Rx>, DecodeError<C::Error>> {
+        loop {
+            let src = &mut self.buffer[self.start..self.end];
+
+            let decoded = match self.codec.framing().decode_frame(src) {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    self.clear();
+
+                    return Err(err.into());
+                }
+            };
+
+            match decoded {
+                Decoded::Frame { payload, len } => {
+                    let result = self.codec.config().deserialize(&src[payload]);
+
+                    self.consume(len);
+
+                    return result.map(Some).map_err(DecodeError::Decode);
+                }
+                Decoded::Skip { len, skipped } => {
+                    self.codec.record_skip(len, skipped);
+
+                    self.consume(len);
+                }
+                Decoded::Incomplete { frame_len } => {
+                    let announced = frame_len.unwrap_or(self.buffered());
+
+                    if announced > N || (frame_len.is_none() && self.buffered() == N) {
+                        self.discard =
+                            Discard::rest(self.codec.framing(), frame_len, self.buffered());
+
+                        self.clear();
+
+                        return Err(DecodeError::FrameExceedsBuffer {
+                            announced,
+                            capacity: N,
+                        });
+                    }
+
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Iterates over the messages that can be decoded from the buffered bytes.
+    #[inline]
+    pub fn messages(&mut self) -> Messages<'_, Codec<Tx, Rx, C>, N> {
+        Messages { decoder: self }
+    }
+}
+
+/// Iterator returned by [`FrameDecoder::messages`].
+pub struct Messages<'a, T, const N: usize> {
+    decoder: &'a mut FrameDecoder<T, N>,
+}
+
+impl<Tx, Rx, C, const N: usize> Iterator for Messages<'_, Codec<Tx, Rx, C>, N>
+where
+    C: Deserializer<Rx>,
+{
+    type Item = Result<Rx, DecodeError<C::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decoder.decode().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::vec::Vec;
+
+    use super::{DecodeError, FrameDecoder};
+    use crate::{
+        checksum::Checksum,
+        codec::Codec,
+        frame::Framing,
+        test::{test_messages, z_test_message, TestMessage},
+    };
+
+    fn encode(codec: &Codec<TestMessage>, items: &[TestMessage]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for item in items {
+            let mut buf = [0_u8; 256];
+
+            let header_len = codec.framing().max_header_len();
+            let payload_len = codec
+                .encode_payload(item, &mut buf[header_len..128])
+                .unwrap();
+            let frame_len = codec.framing().encode_frame(&mut buf, payload_len).unwrap();
+
+            bytes.extend_from_slice(&buf[..frame_len]);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let codec = Codec::<TestMessage>::new();
+        let bytes = encode(&codec, &test_messages());
+
+        let mut decoder = FrameDecoder::<_, 128>::new(codec);
+        let mut collected_items = Vec::new();
+
+        for byte in bytes {
+            assert_eq!(decoder.feed(&[byte]), 1);
+
+            collected_items.extend(decoder.messages().map(Result::unwrap));
+        }
+
+        assert_eq!(collected_items, test_messages());
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn compacts_buffer() {
+        let codec = Codec::<TestMessage>::new();
+        let bytes = encode(&codec, &test_messages());
+
+        let mut decoder = FrameDecoder::<_, 128>::new(codec);
+        let mut collected_items = Vec::new();
+        let mut fed = 0;
+
+        while fed < bytes.len() {
+            fed += decoder.feed(&bytes[fed..(fed + 50).min(bytes.len())]);
+
+            collected_items.extend(decoder.messages().map(Result::unwrap));
+        }
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn frame_exceeds_buffer() {
+        let codec = Codec::<TestMessage>::new();
+        let bytes = encode(&codec, &[z_test_message(), TestMessage::H]);
+
+        let mut decoder = FrameDecoder::<_, 16>::new(codec);
+
+        let fed = decoder.feed(&bytes);
+
+        assert!(matches!(
+            decoder.decode(),
+            Err(DecodeError::FrameExceedsBuffer { capacity: 16, .. })
+        ));
+        assert_eq!(decoder.buffered(), 0);
+
+        // The rest of the oversized frame is dropped, byte by byte.
+        for byte in &bytes[fed..] {
+            assert_eq!(decoder.feed(&[*byte]), 1);
+        }
+
+        assert_eq!(decoder.decode().unwrap(), Some(TestMessage::H));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn cobs_frame_exceeds_buffer() {
+        let codec = Codec::<TestMessage>::new().with_framing(Framing::cobs());
+        let bytes = encode(&codec, &[z_test_message(), TestMessage::A(100)]);
+
+        let mut decoder = FrameDecoder::<_, 16>::new(codec);
+
+        let fed = decoder.feed(&bytes);
+
+        assert!(matches!(
+            decoder.decode(),
+            Err(DecodeError::FrameExceedsBuffer { capacity: 16, .. })
+        ));
+
+        assert_eq!(decoder.feed(&bytes[fed..]), bytes.len() - fed);
+        assert_eq!(decoder.decode().unwrap(), Some(TestMessage::A(100)));
+        assert_eq!(decoder.codec().dropped_frames(), 0);
+    }
+
+    #[test]
+    fn skips_corrupted_cobs_frame() {
+        let codec = Codec::<TestMessage>::new()
+            .with_framing(Framing::cobs().with_checksum(Checksum::Crc16));
+
+        let mut bytes = encode(&codec, &[TestMessage::A(100), TestMessage::B(100)]);
+        bytes[1] ^= 0x01;
+
+        let mut decoder = FrameDecoder::<_, 64>::new(codec);
+
+        decoder.feed(&bytes);
+
+        assert_eq!(decoder.decode().unwrap(), Some(TestMessage::B(100)));
+        assert_eq!(decoder.codec().dropped_frames(), 1);
+    }
+
+    #[test]
+    fn continues_after_invalid_message() {
+        let codec = Codec::<TestMessage>::new();
+        let mut bytes = encode(&codec, &[TestMessage::A(100), TestMessage::H]);
+
+        // An unknown variant.
+        bytes[4] = 100;
+
+        let mut decoder = FrameDecoder::<_, 64>::new(codec);
+
+        decoder.feed(&bytes);
+
+        assert!(matches!(decoder.decode(), Err(DecodeError::Decode(_))));
+        assert_eq!(decoder.decode().unwrap(), Some(TestMessage::H));
+    }
+}
@@ -0,0 +1,398 @@
+//! Framing over `futures_io::AsyncRead` and `futures_io::AsyncWrite`, for runtimes like smol
+//! and async-std.
+
+use core::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use std::{io, vec::Vec};
+
+use futures_core::Stream;
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_sink::Sink;
+
+pub use crate::std_io::{DecodeError, EncodeError};
+use crate::{
+    codec::Codec,
+    serializer::{Deserializer, Serializer},
+    std_io::{encode_frame, unexpected_eof, ReadBuffer},
+};
+
+/// Buffered bytes after which [`FramedWrite`] writes them out before accepting more items.
+const BACKPRESSURE_BOUNDARY: usize = 8 * 1024;
+
+/// A [`Stream`] of messages read from an [`AsyncRead`].
+///
+/// A frame whose payload fails to decode is consumed and yielded as an `Err` item, and the
+/// stream goes on with the next frame. After a framing or I/O error, or a frame cut off by the
+/// end of the input, the stream yields the error and then ends for good.
+///
+/// tokio-util's `FramedRead` differs in both cases: it yields `None` after any decoder error,
+/// payload errors included, and reads on when it is polled again after that `None`.
+pub struct FramedRead<R, T> {
+    reader: R,
+    codec: T,
+    buffer: ReadBuffer,
+    eof: bool,
+    errored: bool,
+}
+
+impl<R: Unpin, T> Unpin for FramedRead<R, T> {}
+
+impl<R, T> FramedRead<R, T> {
+    #[inline]
+    pub const fn new(reader: R, codec: T) -> Self {
+        Self {
+            reader,
+            codec,
+            buffer: ReadBuffer::new(),
+            eof: false,
+            errored: false,
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+}
+
+impl<R, Tx, Rx, C> Stream for FramedRead<R, Codec<Tx, Rx, C>>
+where
+    R: AsyncRead + Unpin,
+    C: Deserializer<Rx>,
+{
+    type Item = Result<Rx, DecodeError<C::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.errored {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.buffer.decode(&mut this.codec) {
+                Ok(Some(message)) => return Poll::Ready(Some(Ok(message))),
+                Ok(None) => {}
+                Err(err) => {
+                    // A frame that failed to decode is consumed, so the framing is still in sync.
+                    this.errored = !matches!(err, DecodeError::Decode(_));
+
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            let read = match ready!(Pin::new(&mut this.reader).poll_read(cx, this.buffer.spare())) {
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    this.errored = true;
+
+                    return Poll::Ready(Some(Err(DecodeError::IO(err))));
+                }
+            };
+
+            if read == 0 {
+                this.eof = true;
+
+                if !this.buffer.is_empty() {
+                    return Poll::Ready(Some(Err(DecodeError::IO(unexpected_eof()))));
+                }
+
+                return Poll::Ready(None);
+            }
+
+            this.buffer.filled(read);
+        }
+    }
+}
+
+/// A [`Sink`] of messages written to an [`AsyncWrite`].
+pub struct FramedWrite<W, T> {
+    writer: W,
+    codec: T,
+    buffer: Vec<u8>,
+    /// Bytes at the start of `buffer` that were written already.
+    written: usize,
+}
+
+impl<W: Unpin, T> Unpin for FramedWrite<W, T> {}
+
+impl<W, T> FramedWrite<W, T> {
+    #[inline]
+    pub const fn new(writer: W, codec: T) -> Self {
+        Self {
+            writer,
+            codec,
+            buffer: Vec::new(),
+            written: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+}
+
+impl<W, T> FramedWrite<W, T>
+where
+    W: AsyncWrite + Unpin,
+{
+    fn poll_write_buffered<E>(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), EncodeError<E>>> {
+        while self.written < self.buffer.len() {
+            let written =
+                ready!(Pin::new(&mut self.writer).poll_write(cx, &self.buffer[self.written..]))?;
+
+            if written == 0 {
+                return Poll::Ready(Err(EncodeError::IO(io::ErrorKind::WriteZero.into())));
+            }
+
+            self.written += written;
+        }
+
+        self.buffer.clear();
+        self.written = 0;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W, Tx, Rx, C> Sink<Tx> for FramedWrite<W, Codec<Tx, Rx, C>>
+where
+    W: AsyncWrite + Unpin,
+    C: Serializer<Tx>,
+{
+    type Error = EncodeError<C::Error>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        if this.buffer.len() - this.written >= BACKPRESSURE_BOUNDARY {
+            return this.poll_write_buffered(cx);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Tx) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+
+        encode_frame(&this.codec, &item, &mut this.buffer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_write_buffered(cx))?;
+
+        Pin::new(&mut this.writer)
+            .poll_flush(cx)
+            .map_err(EncodeError::IO)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_write_buffered(cx))?;
+
+        Pin::new(&mut this.writer)
+            .poll_close(cx)
+            .map_err(EncodeError::IO)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use std::{io, vec::Vec};
+
+    use futures::{executor::block_on, stream, SinkExt, StreamExt};
+    use futures_io::AsyncWrite;
+
+    use super::{DecodeError, FramedRead, FramedWrite};
+    use crate::{
+        checksum::Checksum,
+        codec::Codec,
+        frame::Framing,
+        test::{test_messages, TestMessage},
+    };
+
+    #[test]
+    fn sink_stream() {
+        let framing = Framing::cobs().with_checksum(Checksum::Crc16);
+
+        let mut framed_write = FramedWrite::new(
+            Vec::new(),
+            Codec::<TestMessage>::new().with_framing(framing),
+        );
+
+        block_on(framed_write.send_all(&mut stream::iter(test_messages().into_iter().map(Ok))))
+            .unwrap();
+
+        let bytes = framed_write.into_inner();
+
+        let framed_read = FramedRead::new(
+            &bytes[..],
+            Codec::<TestMessage>::new().with_framing(framing),
+        );
+
+        let collected_items: Vec<_> = block_on(framed_read.map(Result::unwrap).collect::<Vec<_>>());
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn ends_after_framing_error() {
+        let mut framed_write = FramedWrite::new(Vec::new(), Codec::<TestMessage>::new());
+
+        block_on(framed_write.send(TestMessage::C(100, 100))).unwrap();
+        block_on(framed_write.send(TestMessage::H)).unwrap();
+
+        let bytes = framed_write.into_inner();
+
+        let mut framed_read = FramedRead::new(
+            &bytes[..],
+            Codec::<TestMessage>::new().with_max_frame_size(6),
+        );
+
+        assert!(matches!(
+            block_on(framed_read.next()),
+            Some(Err(DecodeError::FrameTooLarge { max: 6, .. }))
+        ));
+        assert!(block_on(framed_read.next()).is_none());
+    }
+
+    #[test]
+    fn continues_after_invalid_message() {
+        let mut framed_write = FramedWrite::new(Vec::new(), Codec::<u32>::new());
+
+        block_on(framed_write.send(100)).unwrap();
+
+        let mut bytes = framed_write.into_inner();
+
+        let mut framed_write = FramedWrite::new(Vec::new(), Codec::<TestMessage>::new());
+
+        block_on(framed_write.send(TestMessage::H)).unwrap();
+
+        bytes.extend(framed_write.into_inner());
+
+        let mut framed_read = FramedRead::new(&bytes[..], Codec::<TestMessage>::new());
+
+        assert!(matches!(
+            block_on(framed_read.next()),
+            Some(Err(DecodeError::Decode(_)))
+        ));
+        assert_eq!(
+            block_on(framed_read.next()).unwrap().unwrap(),
+            TestMessage::H
+        );
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut framed_write = FramedWrite::new(Vec::new(), Codec::<TestMessage>::new());
+
+        block_on(framed_write.send(TestMessage::C(100, 100))).unwrap();
+
+        let mut bytes = framed_write.into_inner();
+        bytes.pop();
+
+        let mut framed_read = FramedRead::new(&bytes[..], Codec::<TestMessage>::new());
+
+        assert!(matches!(
+            block_on(framed_read.next()),
+            Some(Err(DecodeError::IO(err))) if err.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        assert!(block_on(framed_read.next()).is_none());
+    }
+
+    /// Accepts at most three bytes per write.
+    struct Trickle(Vec<u8>);
+
+    impl AsyncWrite for Trickle {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let len = buf.len().min(3);
+
+            self.get_mut().0.extend_from_slice(&buf[..len]);
+
+            Poll::Ready(Ok(len))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn partial_writes() {
+        let mut framed_write = FramedWrite::new(Trickle(Vec::new()), Codec::<TestMessage>::new());
+
+        block_on(framed_write.send_all(&mut stream::iter(test_messages().into_iter().map(Ok))))
+            .unwrap();
+
+        let bytes = framed_write.into_inner().0;
+
+        let framed_read = FramedRead::new(&bytes[..], Codec::<TestMessage>::new());
+
+        let collected_items: Vec<_> = block_on(framed_read.map(Result::unwrap).collect::<Vec<_>>());
+
+        assert_eq!(collected_items, test_messages());
+    }
+}
@@ -4,11 +4,74 @@
 pub mod codec;
 pub use codec::Codec;
 
+pub mod serializer;
+pub use serializer::{Deserializer, Serializer};
+
+pub mod error;
+pub use error::BridgeError;
+
+pub mod checksum;
+pub use checksum::Checksum;
+
+mod cobs;
+
+pub mod frame;
+pub use frame::{ByteOrder, FrameMode, Framing, LengthMode, LengthPrefix};
+
+pub mod recovering;
+pub use recovering::Recovering;
+
+pub mod by_ref;
+pub use by_ref::ByRef;
+
+pub mod borrowed;
+pub use borrowed::BorrowMessage;
+
+pub mod raw;
+pub use raw::{Raw, RawFrame};
+
+pub mod max_size;
+pub use max_size::MaxEncodedSize;
+
+pub mod frame_decoder;
+pub use frame_decoder::FrameDecoder;
+
+#[cfg(feature = "postcard")]
+pub mod postcard;
+#[cfg(feature = "postcard")]
+pub use self::postcard::Postcard;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "cbor")]
+pub use cbor::Cbor;
+
+#[cfg(feature = "std")]
+pub mod std_io;
+#[cfg(feature = "std")]
+pub use std_io::{FramedReader, FramedWriter};
+
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+
+#[cfg(feature = "futures-io")]
+pub mod futures_io;
+
+#[cfg(any(feature = "cody-c", feature = "tokio", feature = "futures-io"))]
+pub mod transport;
+#[cfg(any(feature = "cody-c", feature = "tokio", feature = "futures-io"))]
+pub use transport::{MessageTransport, Transport};
+
+#[cfg(feature = "tokio")]
+pub mod encoded;
+#[cfg(feature = "tokio")]
+pub use encoded::EncodedFrame;
+
 #[cfg(feature = "cody-c")]
-mod cody_c;
+pub mod cody_c;
 
 #[cfg(feature = "tokio")]
-mod tokio;
+pub mod tokio;
 
 #[cfg(feature = "demo")]
 pub mod demo;
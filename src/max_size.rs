@@ -0,0 +1,335 @@
+//! Upper bounds of encoded messages, to size fixed buffers at compile time.
+
+use core::marker::PhantomData;
+
+use crate::frame::Framing;
+
+/// A message whose encoding never exceeds a known number of bytes.
+///
+/// Sizes are those of bincode's standard configuration, the one of `Codec::new`. Other
+/// configurations and formats encode differently, and their sizes are not covered.
+/// Message types implement it with [`max_encoded_size!`](crate::max_encoded_size).
+///
+/// ```
+/// use the_bridge::{max_encoded_size, MaxEncodedSize};
+///
+/// max_encoded_size! {
+///     #[derive(bincode::Encode, bincode::Decode)]
+///     pub enum Command {
+///         Stop,
+///         Move { x: i16, y: i16 },
+///         Blink([u8; 3]),
+///     }
+/// }
+///
+/// // A tag of one byte and two varints of three bytes each, behind a 4 byte header.
+/// assert_eq!(Command::MAX_SIZE, 11);
+///
+/// let buffer = [0_u8; Command::MAX_SIZE];
+/// ```
+pub trait MaxEncodedSize {
+    /// Largest payload of a value, without any framing.
+    const MAX_PAYLOAD_SIZE: usize;
+
+    /// Largest frame of a value with the framing of `Codec::new`, including its 4 byte header.
+    const MAX_SIZE: usize = Framing::new().max_frame_len(Self::MAX_PAYLOAD_SIZE);
+
+    /// Largest frame of a value with `framing`, including its header and trailer.
+    ///
+    /// ```
+    /// use the_bridge::{max_encoded_size, Checksum, Framing, MaxEncodedSize};
+    ///
+    /// max_encoded_size! {
+    ///     pub enum Ping {
+    ///         Ping(u32),
+    ///     }
+    /// }
+    ///
+    /// let framing = Framing::new().with_checksum(Checksum::Crc16);
+    ///
+    /// assert_eq!(Ping::max_frame_size(&framing), Ping::MAX_SIZE + 2);
+    /// ```
+    #[inline]
+    fn max_frame_size(framing: &Framing) -> usize {
+        framing.max_frame_len(Self::MAX_PAYLOAD_SIZE)
+    }
+}
+
+/// Number of bytes of `value` as a bincode varint.
+#[doc(hidden)]
+pub const fn varint_size(value: usize) -> usize {
+    match value {
+        0..=250 => 1,
+        251..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+#[doc(hidden)]
+pub const fn max(sizes: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+
+    while i < sizes.len() {
+        if sizes[i] > max {
+            max = sizes[i];
+        }
+
+        i += 1;
+    }
+
+    max
+}
+
+struct Fits<M, const N: usize>(PhantomData<M>);
+
+impl<M: MaxEncodedSize, const N: usize> Fits<M, N> {
+    const OK: () = assert!(
+        N >= M::MAX_SIZE,
+        "buffer too small for the largest frame of the message"
+    );
+}
+
+/// Returns a zeroed buffer of `N` bytes, failing to compile if [`MaxEncodedSize::MAX_SIZE`]
+/// of `M` does not fit into it.
+///
+/// The check holds for the framing and configuration of `Codec::new` only. It runs when the
+/// call is compiled to code, so `cargo build` reports it but `cargo check` does not.
+///
+/// ```
+/// use the_bridge::{max_size, max_encoded_size};
+///
+/// max_encoded_size! {
+///     pub enum Ping {
+///         Ping(u32),
+///     }
+/// }
+///
+/// let buffer = max_size::buffer::<Ping, 16>();
+/// ```
+///
+/// ```compile_fail
+/// use the_bridge::{max_size, max_encoded_size};
+///
+/// max_encoded_size! {
+///     pub enum Ping {
+///         Ping(u32),
+///     }
+/// }
+///
+/// let buffer = max_size::buffer::<Ping, 8>();
+/// ```
+pub const fn buffer<M: MaxEncodedSize, const N: usize>() -> [u8; N] {
+    #[allow(clippy::let_unit_value)]
+    let () = Fits::<M, N>::OK;
+
+    [0; N]
+}
+
+macro_rules! impl_max_encoded_size {
+    ($($ty:ty => $size:expr),* $(,)?) => {
+        $(
+            impl MaxEncodedSize for $ty {
+                const MAX_PAYLOAD_SIZE: usize = $size;
+            }
+        )*
+    };
+}
+
+impl_max_encoded_size! {
+    () => 0,
+    bool => 1,
+    u8 => 1,
+    i8 => 1,
+    u16 => 3,
+    i16 => 3,
+    u32 => 5,
+    i32 => 5,
+    u64 => 9,
+    i64 => 9,
+    u128 => 17,
+    i128 => 17,
+    usize => 9,
+    isize => 9,
+    f32 => 4,
+    f64 => 8,
+    char => 4,
+}
+
+impl<T> MaxEncodedSize for PhantomData<T> {
+    const MAX_PAYLOAD_SIZE: usize = 0;
+}
+
+impl<T: MaxEncodedSize> MaxEncodedSize for Option<T> {
+    const MAX_PAYLOAD_SIZE: usize = 1 + T::MAX_PAYLOAD_SIZE;
+}
+
+/// Arrays are encoded without their length.
+impl<T: MaxEncodedSize, const N: usize> MaxEncodedSize for [T; N] {
+    const MAX_PAYLOAD_SIZE: usize = N * T::MAX_PAYLOAD_SIZE;
+}
+
+macro_rules! impl_max_encoded_size_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: MaxEncodedSize),+> MaxEncodedSize for ($($name,)+) {
+            const MAX_PAYLOAD_SIZE: usize = 0 $(+ $name::MAX_PAYLOAD_SIZE)+;
+        }
+    };
+}
+
+impl_max_encoded_size_tuple! { A }
+impl_max_encoded_size_tuple! { A B }
+impl_max_encoded_size_tuple! { A B C }
+impl_max_encoded_size_tuple! { A B C D }
+
+/// Defines an enum or a struct and implements [`MaxEncodedSize`] for it, with the sizes of the
+/// bincode configuration of `Codec::new`.
+///
+/// Every field must implement [`MaxEncodedSize`] itself. Generics, explicit discriminants and
+/// tuple structs are not supported:
+///
+/// ```compile_fail
+/// the_bridge::max_encoded_size! {
+///     pub struct Point(i16, i16);
+/// }
+/// ```
+#[macro_export]
+macro_rules! max_encoded_size {
+    (
+        @impl
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident
+                $(( $($(#[$tuple_meta:meta])* $tuple_ty:ty),* $(,)? ))?
+                $({ $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $field_ty:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        impl $crate::max_size::MaxEncodedSize for $name {
+            const MAX_PAYLOAD_SIZE: usize = {
+                let variants: &[&str] = &[$(stringify!($variant)),*];
+
+                $crate::max_size::varint_size(variants.len().saturating_sub(1))
+                    + $crate::max_size::max(&[$(
+                        0
+                        $($(+ <$tuple_ty as $crate::max_size::MaxEncodedSize>::MAX_PAYLOAD_SIZE)*)?
+                        $($(+ <$field_ty as $crate::max_size::MaxEncodedSize>::MAX_PAYLOAD_SIZE)*)?
+                    ),*])
+            };
+        }
+    };
+    (
+        @impl
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $field_ty:ty),* $(,)?
+        }
+    ) => {
+        impl $crate::max_size::MaxEncodedSize for $name {
+            const MAX_PAYLOAD_SIZE: usize =
+                0 $(+ <$field_ty as $crate::max_size::MaxEncodedSize>::MAX_PAYLOAD_SIZE)*;
+        }
+    };
+    (@impl $($rest:tt)*) => {
+        compile_error!(
+            "max_encoded_size! supports only non-generic enums and structs with named fields"
+        );
+    };
+    // The item is passed on untouched, so that derives on it see the tokens as written.
+    ($($item:tt)*) => {
+        $($item)*
+
+        $crate::max_encoded_size! { @impl $($item)* }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::MaxEncodedSize;
+    use crate::{checksum::Checksum, codec::Codec, frame::Framing};
+
+    crate::max_encoded_size! {
+        #[derive(Debug, bincode::Encode, bincode::Decode)]
+        struct Position {
+            x: i32,
+            y: i32,
+        }
+    }
+
+    crate::max_encoded_size! {
+        #[derive(Debug, bincode::Encode, bincode::Decode)]
+        enum Message {
+            Empty,
+            Flag(bool),
+            Move { to: Position, speed: Option<u16> },
+            Raw([u8; 16], char),
+            Wide(u128, i64),
+        }
+    }
+
+    fn frame_len(message: &Message) -> usize {
+        let mut dst = [0_u8; 64];
+
+        4 + Codec::<Message>::new()
+            .encode_payload(message, &mut dst)
+            .unwrap()
+    }
+
+    #[test]
+    fn bounds_largest_values() {
+        assert_eq!(Position::MAX_PAYLOAD_SIZE, 10);
+        assert_eq!(Message::MAX_SIZE, 4 + 1 + 17 + 9);
+
+        let largest = [
+            Message::Empty,
+            Message::Flag(true),
+            Message::Move {
+                to: Position {
+                    x: i32::MIN,
+                    y: i32::MIN,
+                },
+                speed: Some(u16::MAX),
+            },
+            Message::Raw([u8::MAX; 16], char::MAX),
+            Message::Wide(u128::MAX, i64::MIN),
+        ];
+
+        for message in &largest {
+            assert!(frame_len(message) <= Message::MAX_SIZE, "{message:?}");
+        }
+
+        assert_eq!(frame_len(&largest[4]), Message::MAX_SIZE);
+    }
+
+    #[test]
+    fn bounds_frames_of_any_framing() {
+        let message = Message::Wide(u128::MAX, i64::MIN);
+
+        for framing in [
+            Framing::new().with_checksum(Checksum::Crc32),
+            Framing::cobs().with_checksum(Checksum::Crc32),
+        ] {
+            let codec = Codec::<Message>::new().with_framing(framing);
+
+            let mut buf = [0_u8; 64];
+
+            let payload_len = codec
+                .encode_payload(&message, &mut buf[framing.max_header_len()..])
+                .unwrap();
+            let frame_len = framing.encode_frame(&mut buf, payload_len).unwrap();
+
+            assert!(
+                frame_len <= Message::max_frame_size(&framing),
+                "{framing:?}"
+            );
+        }
+
+        assert_eq!(
+            Message::max_frame_size(&Framing::new().with_checksum(Checksum::Crc32)),
+            Message::MAX_SIZE + 4
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Payloads encoded with postcard instead of bincode.
+
+use postcard::ser_flavors::Flavor;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::serializer::{Deserializer, Serializer, Writer};
+
+/// The postcard format, for messages implementing serde's `Serialize` and `Deserialize`.
+///
+/// Frames are the same as with bincode, only the payloads differ.
+///
+/// ```
+/// use the_bridge::{Codec, Postcard};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Reading {
+///     sensor: u8,
+///     value: f32,
+/// }
+///
+/// let codec = Codec::<Reading, Reading, Postcard>::postcard();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Postcard;
+
+struct WriterFlavor<'a, W>(&'a mut W);
+
+impl<W: Writer> Flavor for WriterFlavor<'_, W> {
+    type Output = ();
+
+    #[inline]
+    fn try_extend(&mut self, data: &[u8]) -> postcard::Result<()> {
+        self.0
+            .write(data)
+            .map_err(|_| postcard::Error::SerializeBufferFull)
+    }
+
+    #[inline]
+    fn try_push(&mut self, data: u8) -> postcard::Result<()> {
+        self.try_extend(&[data])
+    }
+
+    #[inline]
+    fn finalize(self) -> postcard::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> Serializer<T> for Postcard
+where
+    T: Serialize + ?Sized,
+{
+    type Error = postcard::Error;
+
+    fn serialize<W: Writer>(&self, item: &T, dst: &mut W) -> Result<(), Self::Error> {
+        postcard::serialize_with_flavor(item, WriterFlavor(dst))
+    }
+}
+
+impl<T> Deserializer<T> for Postcard
+where
+    T: DeserializeOwned,
+{
+    type Error = postcard::Error;
+
+    fn deserialize(&self, src: &[u8]) -> Result<T, Self::Error> {
+        postcard::from_bytes(src)
+    }
+}
@@ -0,0 +1,23 @@
+//! Frames forwarded without decoding their payload.
+
+/// Message type of a raw codec, `Codec<RawFrame, RawFrame, Raw>`, created by `Codec::raw`.
+///
+/// A raw codec yields and accepts the payload of every frame as bytes: `Bytes` with tokio and
+/// `&[u8]` with cody-c. Typed codecs convert between payloads and messages with
+/// `Codec::decode_payload` and `Codec::encode_payload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFrame {}
+
+/// Format of a raw codec.
+///
+/// Payloads are never looked at, so a raw codec forwards frames of any format with the same
+/// framing, and only fails on framing errors.
+///
+/// ```
+/// use the_bridge::Codec;
+///
+/// let proxy = Codec::raw();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Raw;
@@ -0,0 +1,32 @@
+//! Skip-and-continue decoding.
+
+/// Wraps a [`Codec`](crate::Codec) so that a frame whose payload fails to decode does not end
+/// the stream.
+///
+/// The bad frame is consumed and yielded as an `Err` item, then decoding continues with the
+/// next frame. Framing errors are still returned as errors. Encoding is left unchanged.
+pub struct Recovering<T> {
+    inner: T,
+}
+
+impl<T> Recovering<T> {
+    #[inline]
+    pub const fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
@@ -0,0 +1,304 @@
+//! Formats that turn messages into frame payloads and back.
+//!
+//! The `C` parameter of a [`Codec`](crate::Codec) is its format. Any bincode configuration is
+//! one, and so are `Postcard` and `Cbor` with the `postcard` and `cbor` features. Other formats
+//! implement [`Serializer`] and [`Deserializer`] and get the framing and every backend for free.
+//!
+//! ```
+//! use the_bridge::{
+//!     serializer::{BufferFull, Deserializer, Serializer, Writer},
+//!     Codec, FrameDecoder,
+//! };
+//!
+//! /// A reading laid out like the C struct of a legacy device.
+//! #[derive(Debug, PartialEq)]
+//! struct Reading {
+//!     id: u16,
+//!     value: i32,
+//! }
+//!
+//! #[derive(Clone, Copy)]
+//! struct Legacy;
+//!
+//! #[derive(Debug)]
+//! struct TooShort;
+//!
+//! impl Serializer<Reading> for Legacy {
+//!     type Error = BufferFull;
+//!
+//!     fn serialize<W: Writer>(&self, item: &Reading, dst: &mut W) -> Result<(), BufferFull> {
+//!         dst.write(&item.id.to_le_bytes())?;
+//!         dst.write(&item.value.to_le_bytes())
+//!     }
+//! }
+//!
+//! impl Deserializer<Reading> for Legacy {
+//!     type Error = TooShort;
+//!
+//!     fn deserialize(&self, src: &[u8]) -> Result<Reading, TooShort> {
+//!         match src {
+//!             [a, b, c, d, e, f, ..] => Ok(Reading {
+//!                 id: u16::from_le_bytes([*a, *b]),
+//!                 value: i32::from_le_bytes([*c, *d, *e, *f]),
+//!             }),
+//!             _ => Err(TooShort),
+//!         }
+//!     }
+//! }
+//!
+//! let codec = Codec::<Reading, Reading, _>::with_config(Legacy);
+//!
+//! let mut buffer = [0; 32];
+//! let size = codec.encode_payload(&Reading { id: 7, value: -1 }, &mut buffer).unwrap();
+//!
+//! assert_eq!(codec.decode_payload(&buffer[..size]).unwrap(), Reading { id: 7, value: -1 });
+//!
+//! let mut decoder = FrameDecoder::<_, 64>::new(codec);
+//!
+//! decoder.feed(&[0, 0, 0, 10, 7, 0, 255, 255, 255, 255]);
+//!
+//! assert_eq!(decoder.decode().unwrap(), Some(Reading { id: 7, value: -1 }));
+//! ```
+
+use core::fmt;
+
+use bincode::{
+    config::Config,
+    de::Decode,
+    enc::{
+        write::{SizeWriter, Writer as BincodeWriter},
+        Encode,
+    },
+};
+
+/// The destination of a payload ran out of space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BufferFull;
+
+impl fmt::Display for BufferFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "buffer full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferFull {}
+
+/// Where a [`Serializer`] writes a payload to.
+pub trait Writer {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferFull>;
+}
+
+/// Writes a payload into a slice.
+pub(crate) struct SliceWriter<'a> {
+    slice: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    #[inline]
+    pub(crate) fn new(slice: &'a mut [u8]) -> Self {
+        Self { slice, len: 0 }
+    }
+
+    /// Number of bytes written so far.
+    #[inline]
+    pub(crate) const fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Writer for SliceWriter<'_> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        let end = self.len + bytes.len();
+
+        self.slice
+            .get_mut(self.len..end)
+            .ok_or(BufferFull)?
+            .copy_from_slice(bytes);
+
+        self.len = end;
+
+        Ok(())
+    }
+}
+
+/// Counts the bytes of a payload without keeping them.
+struct LenWriter {
+    len: usize,
+}
+
+impl Writer for LenWriter {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        self.len += bytes.len();
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Writer for std::vec::Vec<u8> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        self.extend_from_slice(bytes);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Writer for tokio_util::bytes::BytesMut {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferFull> {
+        self.extend_from_slice(bytes);
+
+        Ok(())
+    }
+}
+
+/// Writes messages of type `T` as payloads.
+///
+/// A format that is `Copy` can be used with the `const` builders of [`Codec`](crate::Codec).
+pub trait Serializer<T: ?Sized> {
+    type Error;
+
+    /// Writes the payload of `item` to `dst`.
+    ///
+    /// Fails with its own error when `dst` is full, which callers report like any other
+    /// encoding error.
+    fn serialize<W: Writer>(&self, item: &T, dst: &mut W) -> Result<(), Self::Error>;
+
+    /// Returns the number of bytes [`Serializer::serialize`] writes for `item`.
+    ///
+    /// Serializes `item` without keeping the bytes, unless the format has a faster way.
+    fn encoded_len(&self, item: &T) -> Result<usize, Self::Error> {
+        let mut writer = LenWriter { len: 0 };
+
+        self.serialize(item, &mut writer)?;
+
+        Ok(writer.len)
+    }
+}
+
+/// Reads messages of type `T` from payloads.
+pub trait Deserializer<T> {
+    type Error;
+
+    /// Reads a message from the payload of a single frame.
+    fn deserialize(&self, src: &[u8]) -> Result<T, Self::Error>;
+}
+
+struct BincodeAdapter<'a, W>(&'a mut W);
+
+impl<W: Writer> BincodeWriter for BincodeAdapter<'_, W> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+        self.0
+            .write(bytes)
+            .map_err(|BufferFull| bincode::error::EncodeError::UnexpectedEnd)
+    }
+}
+
+impl<C, T> Serializer<T> for C
+where
+    C: Config,
+    T: Encode + ?Sized,
+{
+    type Error = bincode::error::EncodeError;
+
+    fn serialize<W: Writer>(&self, item: &T, dst: &mut W) -> Result<(), Self::Error> {
+        bincode::encode_into_writer(item, BincodeAdapter(dst), *self)
+    }
+
+    fn encoded_len(&self, item: &T) -> Result<usize, Self::Error> {
+        let mut writer = SizeWriter::default();
+
+        bincode::encode_into_writer(item, &mut writer, *self)?;
+
+        Ok(writer.bytes_written)
+    }
+}
+
+impl<C, T> Deserializer<T> for C
+where
+    C: Config,
+    T: Decode,
+{
+    type Error = bincode::error::DecodeError;
+
+    fn deserialize(&self, src: &[u8]) -> Result<T, Self::Error> {
+        bincode::decode_from_slice(src, *self).map(|(item, _)| item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    use std::vec::Vec;
+
+    use super::{BufferFull, Deserializer, Serializer, Writer};
+    use crate::{codec::Codec, frame::Framing, frame_decoder::FrameDecoder};
+
+    /// Big endian `u32`s, to check that the framing does not care about the format.
+    #[derive(Clone, Copy)]
+    struct Raw;
+
+    #[derive(Debug, PartialEq)]
+    enum RawError {
+        BufferFull,
+        Length(usize),
+    }
+
+    impl Serializer<u32> for Raw {
+        type Error = RawError;
+
+        fn serialize<W: Writer>(&self, item: &u32, dst: &mut W) -> Result<(), RawError> {
+            dst.write(&item.to_be_bytes())
+                .map_err(|BufferFull| RawError::BufferFull)
+        }
+    }
+
+    impl Deserializer<u32> for Raw {
+        type Error = RawError;
+
+        fn deserialize(&self, src: &[u8]) -> Result<u32, RawError> {
+            src.try_into()
+                .map(u32::from_be_bytes)
+                .map_err(|_| RawError::Length(src.len()))
+        }
+    }
+
+    #[test]
+    fn custom_format() {
+        let codec = Codec::<u32, u32, _>::with_config(Raw).with_framing(Framing::cobs());
+
+        let mut dst = [0_u8; 8];
+
+        assert_eq!(codec.encode_payload(&0xdead_beef, &mut dst), Ok(4));
+        assert_eq!(dst[..4], [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(codec.encoded_len(&0xdead_beef), Ok(4));
+        assert_eq!(
+            codec.encode_payload(&1, &mut dst[..3]),
+            Err(RawError::BufferFull)
+        );
+
+        let mut decoder = FrameDecoder::<_, 32>::new(codec);
+
+        // A COBS frame of 3 bytes, followed by one of 4.
+        decoder.feed(&[0x04, 1, 2, 3, 0x00, 0x05, 1, 2, 3, 4, 0x00]);
+
+        let messages: Vec<_> = decoder.messages().collect();
+
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            messages[0],
+            Err(crate::frame_decoder::DecodeError::Decode(RawError::Length(
+                3
+            )))
+        ));
+        assert!(matches!(messages[1], Ok(0x0102_0304)));
+    }
+}
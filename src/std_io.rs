@@ -0,0 +1,519 @@
+//! Blocking framing over `std::io::Read` and `std::io::Write`.
+
+use core::fmt;
+use std::{
+    io::{self, Read, Write},
+    vec::Vec,
+};
+
+use crate::{
+    codec::Codec,
+    error::BridgeError,
+    frame::{self, Decoded},
+    serializer::{Deserializer, Serializer},
+};
+
+/// Bytes requested from the reader at least, when the size of the next frame is not known.
+const READ_CHUNK: usize = 1024;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum EncodeError<E = bincode::error::EncodeError> {
+    IO(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] io::Error),
+    Encode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
+    MessageTooBig,
+    FrameTooLarge { size: usize, max: usize },
+}
+
+impl<E> From<io::Error> for EncodeError<E> {
+    fn from(err: io::Error) -> Self {
+        EncodeError::IO(err)
+    }
+}
+
+impl<E> From<frame::EncodeError> for EncodeError<E> {
+    fn from(err: frame::EncodeError) -> Self {
+        match err {
+            frame::EncodeError::MessageTooBig => EncodeError::MessageTooBig,
+            frame::EncodeError::FrameTooLarge { size, max } => {
+                EncodeError::FrameTooLarge { size, max }
+            }
+        }
+    }
+}
+
+impl<E> fmt::Display for EncodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::IO(_) => write!(f, "I/O error"),
+            EncodeError::Encode(_) => write!(f, "failed to encode message"),
+            EncodeError::MessageTooBig => write!(f, "message too big for the length prefix"),
+            EncodeError::FrameTooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for EncodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::IO(err) => Some(err),
+            EncodeError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<EncodeError<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: EncodeError<E>) -> Self {
+        match err {
+            EncodeError::IO(err) => BridgeError::IO(err),
+            EncodeError::Encode(err) => err.into(),
+            EncodeError::MessageTooBig => BridgeError::MessageTooBig,
+            EncodeError::FrameTooLarge { size, max } => BridgeError::FrameTooLarge { size, max },
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError<E = bincode::error::DecodeError> {
+    IO(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] io::Error),
+    InvalidFrameSize,
+    FrameTooLarge { announced: usize, max: usize },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
+}
+
+impl<E> From<io::Error> for DecodeError<E> {
+    fn from(err: io::Error) -> Self {
+        DecodeError::IO(err)
+    }
+}
+
+impl<E> From<frame::DecodeError> for DecodeError<E> {
+    fn from(err: frame::DecodeError) -> Self {
+        match err {
+            frame::DecodeError::InvalidFrameSize => DecodeError::InvalidFrameSize,
+            frame::DecodeError::FrameTooLarge { announced, max } => {
+                DecodeError::FrameTooLarge { announced, max }
+            }
+            frame::DecodeError::ChecksumMismatch => DecodeError::ChecksumMismatch,
+        }
+    }
+}
+
+impl<E> fmt::Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::IO(_) => write!(f, "I/O error"),
+            DecodeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            DecodeError::FrameTooLarge { announced, max } => write!(
+                f,
+                "announced frame of {announced} bytes exceeds the maximum of {max} bytes"
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            DecodeError::Decode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for DecodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::IO(err) => Some(err),
+            DecodeError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<DecodeError<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: DecodeError<E>) -> Self {
+        match err {
+            DecodeError::IO(err) => BridgeError::IO(err),
+            DecodeError::InvalidFrameSize => BridgeError::InvalidFrameSize,
+            DecodeError::FrameTooLarge { announced, max } => BridgeError::FrameTooLarge {
+                size: announced,
+                max,
+            },
+            DecodeError::ChecksumMismatch => BridgeError::ChecksumMismatch,
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
+
+/// Buffered bytes of a reader, shared by the blocking and the async readers.
+pub(crate) struct ReadBuffer {
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+    frame_len: Option<usize>,
+}
+
+impl ReadBuffer {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            start: 0,
+            end: 0,
+            frame_len: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Decodes the next message from the buffered bytes.
+    ///
+    /// Returns `Ok(None)` until a complete frame is buffered. A frame that fails to decode is
+    /// consumed.
+    pub(crate) fn decode<Tx, Rx, C>(
+        &mut self,
+        codec: &mut Codec<Tx, Rx, C>,
+    ) -> Result<Option<Rx>, DecodeError<C::Error>>
+    where
+        C: Deserializer<Rx>,
+    {
+        loop {
+            let src = &mut self.buffer[self.start..self.end];
+
+            match codec.framing().decode_frame(src)? {
+                Decoded::Frame { payload, len } => {
+                    let result = codec.config().deserialize(&src[payload]);
+
+                    self.start += len;
+
+                    return result.map(Some).map_err(DecodeError::Decode);
+                }
+                Decoded::Skip { len, skipped } => {
+                    codec.record_skip(len, skipped);
+
+                    self.start += len;
+                }
+                Decoded::Incomplete { frame_len } => {
+                    self.frame_len = frame_len;
+
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Returns the space to read into, towards the size of the incomplete frame if known.
+    pub(crate) fn spare(&mut self) -> &mut [u8] {
+        if self.start > 0 {
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        let wanted = self
+            .frame_len
+            .map_or(READ_CHUNK, |frame_len| frame_len.saturating_sub(self.end));
+
+        // A corrupted header must not make us allocate the whole announced size at once.
+        let len = self.end + wanted.clamp(READ_CHUNK, READ_CHUNK * 64);
+
+        if self.buffer.len() < len {
+            self.buffer.resize(len, 0);
+        }
+
+        &mut self.buffer[self.end..]
+    }
+
+    /// Marks `read` bytes of [`ReadBuffer::spare`] as buffered.
+    #[inline]
+    pub(crate) fn filled(&mut self, read: usize) {
+        self.end += read;
+    }
+}
+
+pub(crate) fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "bytes remaining on stream")
+}
+
+/// Appends the frame of `item` to `dst`, leaving `dst` untouched on failure.
+pub(crate) fn encode_frame<Tx, Rx, C>(
+    codec: &Codec<Tx, Rx, C>,
+    item: &Tx,
+    dst: &mut Vec<u8>,
+) -> Result<(), EncodeError<C::Error>>
+where
+    C: Serializer<Tx>,
+{
+    let start = dst.len();
+
+    let result = append_frame(codec, item, dst, start);
+
+    if result.is_err() {
+        dst.truncate(start);
+    }
+
+    result
+}
+
+fn append_frame<Tx, Rx, C>(
+    codec: &Codec<Tx, Rx, C>,
+    item: &Tx,
+    dst: &mut Vec<u8>,
+    start: usize,
+) -> Result<(), EncodeError<C::Error>>
+where
+    C: Serializer<Tx>,
+{
+    let framing = codec.framing();
+
+    let payload_start = start + framing.max_header_len();
+
+    dst.resize(payload_start, 0);
+
+    codec
+        .config()
+        .serialize(item, dst)
+        .map_err(EncodeError::Encode)?;
+
+    let message_size = dst.len() - payload_start;
+
+    dst.resize(dst.len() + framing.reserved_after(message_size), 0);
+
+    let packet_size = framing.encode_frame(&mut dst[start..], message_size)?;
+
+    dst.truncate(start + packet_size);
+
+    Ok(())
+}
+
+/// Reads messages from a blocking reader.
+pub struct FramedReader<R, T> {
+    reader: R,
+    codec: T,
+    buffer: ReadBuffer,
+}
+
+impl<R, T> FramedReader<R, T> {
+    #[inline]
+    pub const fn new(reader: R, codec: T) -> Self {
+        Self {
+            reader,
+            codec,
+            buffer: ReadBuffer::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+}
+
+impl<R, Tx, Rx, C> FramedReader<R, Codec<Tx, Rx, C>>
+where
+    R: Read,
+    C: Deserializer<Rx>,
+{
+    /// Blocks until the next message is read.
+    ///
+    /// Returns `Ok(None)` when the reader reaches the end of its input between two frames.
+    pub fn recv(&mut self) -> Result<Option<Rx>, DecodeError<C::Error>> {
+        loop {
+            if let Some(message) = self.buffer.decode(&mut self.codec)? {
+                return Ok(Some(message));
+            }
+
+            let read = loop {
+                match self.reader.read(self.buffer.spare()) {
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    result => break result?,
+                }
+            };
+
+            if read == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+
+                return Err(DecodeError::IO(unexpected_eof()));
+            }
+
+            self.buffer.filled(read);
+        }
+    }
+}
+
+/// Writes messages to a blocking writer.
+pub struct FramedWriter<W, T> {
+    writer: W,
+    codec: T,
+    buffer: Vec<u8>,
+}
+
+impl<W, T> FramedWriter<W, T> {
+    #[inline]
+    pub const fn new(writer: W, codec: T) -> Self {
+        Self {
+            writer,
+            codec,
+            buffer: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    pub const fn codec(&self) -> &T {
+        &self.codec
+    }
+
+    #[inline]
+    pub fn codec_mut(&mut self) -> &mut T {
+        &mut self.codec
+    }
+}
+
+impl<W, Tx, Rx, C> FramedWriter<W, Codec<Tx, Rx, C>>
+where
+    W: Write,
+    C: Serializer<Tx>,
+{
+    /// Writes the frame of `item` and flushes the writer.
+    pub fn send(&mut self, item: &Tx) -> Result<(), EncodeError<C::Error>> {
+        self.buffer.clear();
+
+        encode_frame(&self.codec, item, &mut self.buffer)?;
+
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, vec::Vec};
+
+    use super::{DecodeError, FramedReader, FramedWriter};
+    use crate::{
+        checksum::Checksum,
+        codec::Codec,
+        frame::Framing,
+        test::{test_messages, TestMessage},
+    };
+
+    #[test]
+    fn send_recv() {
+        let framing = Framing::new().with_checksum(Checksum::Crc32);
+
+        let mut writer = FramedWriter::new(
+            Vec::new(),
+            Codec::<TestMessage>::new().with_framing(framing),
+        );
+
+        for item in test_messages() {
+            writer.send(&item).unwrap();
+        }
+
+        let bytes = writer.into_inner();
+
+        let mut reader = FramedReader::new(
+            Cursor::new(bytes),
+            Codec::<TestMessage>::new().with_framing(framing),
+        );
+
+        let mut collected_items = Vec::new();
+
+        while let Some(item) = reader.recv().unwrap() {
+            collected_items.push(item);
+        }
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut writer = FramedWriter::new(Vec::new(), Codec::<TestMessage>::new());
+
+        writer.send(&TestMessage::C(100, 100)).unwrap();
+
+        let mut bytes = writer.into_inner();
+        bytes.pop();
+
+        let mut reader = FramedReader::new(Cursor::new(bytes), Codec::<TestMessage>::new());
+
+        assert!(matches!(
+            reader.recv(),
+            Err(DecodeError::IO(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_interop() {
+        use tokio_util::{bytes::BytesMut, codec::Decoder};
+
+        let mut writer = FramedWriter::new(Vec::new(), Codec::<TestMessage>::new());
+
+        for item in test_messages() {
+            writer.send(&item).unwrap();
+        }
+
+        let mut buf = BytesMut::from(&writer.into_inner()[..]);
+        let mut codec = Codec::<TestMessage>::new();
+
+        let mut collected_items = Vec::new();
+
+        while let Some(item) = codec.decode(&mut buf).unwrap() {
+            collected_items.push(item);
+        }
+
+        assert_eq!(collected_items, test_messages());
+    }
+}
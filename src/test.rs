@@ -1,7 +1,11 @@
 extern crate std;
 use std::{boxed::Box, string::String, vec::Vec};
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode, PartialEq)]
+use crate::borrowed::BorrowMessage;
+
+#[derive(
+    Debug, Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize, PartialEq,
+)]
 pub enum TestMessage {
     A(u8),
     B(i32),
@@ -78,6 +82,16 @@ pub fn test_messages() -> Vec<TestMessage> {
     ]
 }
 
+#[derive(Debug, Clone, bincode::Encode, bincode::BorrowDecode, PartialEq)]
+pub enum BorrowedTestMessage<'a> {
+    Text(&'a str),
+    Blob(u32, &'a [u8]),
+}
+
+impl BorrowMessage for BorrowedTestMessage<'static> {
+    type Message<'buf> = BorrowedTestMessage<'buf>;
+}
+
 #[cfg(all(feature = "tokio", feature = "cody-c"))]
 mod comp {
     use cody_c::{tokio::Compat, FramedRead as CodyFramedRead, FramedWrite as CodyFramedWrite};
@@ -162,3 +176,88 @@ mod comp {
         assert_eq!(collected_items, items);
     }
 }
+
+#[cfg(all(feature = "tokio", feature = "futures-io"))]
+mod futures_comp {
+    use futures::{stream, SinkExt, StreamExt};
+    use tokio_util::{
+        codec::{FramedRead as TokioFramedRead, FramedWrite as TokioFramedWrite},
+        compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt},
+    };
+
+    use super::*;
+    use crate::{
+        codec::Codec,
+        futures_io::{FramedRead as FuturesFramedRead, FramedWrite as FuturesFramedWrite},
+    };
+
+    #[tokio::test]
+    async fn futures_sink_tokio_stream() {
+        let items = test_messages();
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec = Codec::<TestMessage>::new();
+            let mut framed_write = FuturesFramedWrite::new(write.compat_write(), codec);
+
+            framed_write
+                .send_all(&mut stream::iter(items.into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage>::new();
+        let framed_read = TokioFramedRead::new(read, codec);
+
+        let collected_items: Vec<_> = framed_read
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        handle.await.unwrap();
+
+        let items = test_messages();
+
+        assert_eq!(collected_items, items);
+    }
+
+    #[tokio::test]
+    async fn tokio_sink_futures_stream() {
+        let items = test_messages();
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec = Codec::<TestMessage>::new();
+            let mut framed_write = TokioFramedWrite::new(write, codec);
+
+            framed_write
+                .send_all(&mut stream::iter(items.into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage>::new();
+        let framed_read = FuturesFramedRead::new(read.compat(), codec);
+
+        let collected_items: Vec<_> = framed_read
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        handle.await.unwrap();
+
+        let items = test_messages();
+
+        assert_eq!(collected_items, items);
+    }
+}
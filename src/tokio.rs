@@ -1,106 +1,489 @@
-use crate::codec::Codec;
+//! `tokio_util::codec` integration and its errors.
+
+use core::{convert::Infallible, fmt, ops::Range};
+
+use bincode::config::{Config, Configuration};
+
+use crate::{
+    borrowed::{BorrowMessage, Borrowed},
+    by_ref::ByRef,
+    codec::Codec,
+    encoded::EncodedFrame,
+    error::BridgeError,
+    frame::{self, Decoded},
+    raw::{Raw, RawFrame},
+    recovering::Recovering,
+    serializer::{Deserializer, Serializer},
+};
 use tokio_util::{
-    bytes::{Buf, BufMut, BytesMut},
+    bytes::{Buf, BufMut, Bytes, BytesMut},
     codec::{Decoder, Encoder},
 };
 
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
-pub enum EncodeError {
-    IO(std::io::Error),
-    Encode(bincode::error::EncodeError),
+pub enum EncodeError<E = bincode::error::EncodeError> {
+    IO(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] std::io::Error),
+    Encode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
     MessageTooBig,
+    FrameTooLarge { size: usize, max: usize },
+    FramingMismatch,
 }
 
-impl From<std::io::Error> for EncodeError {
+impl<E> From<std::io::Error> for EncodeError<E> {
     fn from(err: std::io::Error) -> Self {
         EncodeError::IO(err)
     }
 }
 
-impl<M> Encoder<M> for Codec<M>
+/// Appends a frame to `dst` with the payload written by `write_payload`,
+/// leaving `dst` unchanged on failure.
+///
+/// When the size of the payload is known up front, the whole frame is reserved at once and
+/// a frame over the maximum size fails before anything is written.
+fn encode_with<Tx, Rx, C, E>(
+    codec: &Codec<Tx, Rx, C>,
+    dst: &mut BytesMut,
+    message_size: Option<usize>,
+    write_payload: impl FnOnce(&mut BytesMut) -> Result<usize, EncodeError<E>>,
+) -> Result<(), EncodeError<E>> {
+    let framing = codec.framing();
+
+    if let Some(message_size) = message_size {
+        framing.check_payload_len(message_size)?;
+
+        dst.reserve(framing.max_header_len() + message_size + framing.reserved_after(message_size));
+    }
+
+    let start_len = dst.len();
+
+    dst.put_bytes(0, framing.max_header_len());
+
+    let message_size = match write_payload(dst) {
+        Ok(message_size) => message_size,
+        Err(err) => {
+            dst.truncate(start_len);
+
+            return Err(err);
+        }
+    };
+
+    dst.put_bytes(0, framing.reserved_after(message_size));
+
+    match framing.encode_frame(&mut dst[start_len..], message_size) {
+        Ok(packet_size) => {
+            dst.truncate(start_len + packet_size);
+
+            Ok(())
+        }
+        Err(err) => {
+            dst.truncate(start_len);
+
+            Err(err.into())
+        }
+    }
+}
+
+fn encode_item<Tx, Rx, C>(
+    codec: &Codec<Tx, Rx, C>,
+    item: &Tx,
+    dst: &mut BytesMut,
+) -> Result<(), EncodeError<C::Error>>
 where
-    M: bincode::Encode,
+    C: Serializer<Tx>,
 {
+    encode_with(codec, dst, None, |dst| {
+        let start_len = dst.len();
+
+        codec
+            .config()
+            .serialize(item, dst)
+            .map_err(EncodeError::Encode)?;
+
+        Ok(dst.len() - start_len)
+    })
+}
+
+impl<Tx, Rx, C> Encoder<Tx> for Codec<Tx, Rx, C>
+where
+    C: Serializer<Tx>,
+{
+    type Error = EncodeError<C::Error>;
+
+    fn encode(&mut self, item: Tx, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode_item(self, &item, dst)
+    }
+}
+
+impl<'a, Tx, Rx, C> Encoder<&'a Tx> for ByRef<Codec<Tx, Rx, C>>
+where
+    C: Serializer<Tx>,
+{
+    type Error = EncodeError<C::Error>;
+
+    fn encode(&mut self, item: &'a Tx, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode_item(self.inner(), item, dst)
+    }
+}
+
+impl<Tx, Rx, C> Codec<Tx, Rx, C>
+where
+    C: Serializer<Tx>,
+{
+    /// Encodes `item` once into a frame that can be written to any codec with the same framing.
+    pub fn encode_frame(&self, item: &Tx) -> Result<EncodedFrame<Tx, C>, EncodeError<C::Error>> {
+        let mut dst = BytesMut::new();
+
+        encode_item(self, item, &mut dst)?;
+
+        Ok(EncodedFrame::new(dst.freeze(), *self.framing()))
+    }
+}
+
+impl<Tx, Rx, C> Encoder<EncodedFrame<Tx, C>> for ByRef<Codec<Tx, Rx, C>> {
     type Error = EncodeError;
 
-    fn encode(&mut self, item: M, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let start_len = dst.len();
+    fn encode(&mut self, item: EncodedFrame<Tx, C>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.framing() != self.inner().framing() {
+            return Err(EncodeError::FramingMismatch);
+        }
+
+        dst.extend_from_slice(item.bytes());
+
+        Ok(())
+    }
+}
 
-        dst.put_u32(0);
+impl Encoder<Bytes> for Codec<RawFrame, RawFrame, Raw> {
+    type Error = EncodeError<Infallible>;
 
-        let message_size =
-            bincode::encode_into_std_write(item, &mut dst.writer(), bincode::config::standard())
-                .map_err(EncodeError::Encode)?;
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode_with(self, dst, Some(item.len()), |dst| {
+            dst.extend_from_slice(&item);
 
-        if message_size > u32::MAX as usize {
-            return Err(EncodeError::MessageTooBig);
+            Ok(item.len())
+        })
+    }
+}
+
+impl<E> From<frame::EncodeError> for EncodeError<E> {
+    fn from(err: frame::EncodeError) -> Self {
+        match err {
+            frame::EncodeError::MessageTooBig => EncodeError::MessageTooBig,
+            frame::EncodeError::FrameTooLarge { size, max } => {
+                EncodeError::FrameTooLarge { size, max }
+            }
         }
+    }
+}
 
-        let packet_size = (dst.len() - start_len) as u32;
-        let packet_size_bytes = packet_size.to_be_bytes();
+impl<E> fmt::Display for EncodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::IO(_) => write!(f, "I/O error"),
+            EncodeError::Encode(_) => write!(f, "failed to encode message"),
+            EncodeError::MessageTooBig => write!(f, "message too big for the length prefix"),
+            EncodeError::FrameTooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+            EncodeError::FramingMismatch => {
+                write!(f, "frame encoded with a different framing than the codec's")
+            }
+        }
+    }
+}
 
-        dst[start_len..start_len + 4].copy_from_slice(&packet_size_bytes);
+impl<E: std::error::Error + 'static> std::error::Error for EncodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::IO(err) => Some(err),
+            EncodeError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
-        Ok(())
+impl<E> From<EncodeError<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: EncodeError<E>) -> Self {
+        match err {
+            EncodeError::IO(err) => BridgeError::IO(err),
+            EncodeError::Encode(err) => err.into(),
+            EncodeError::MessageTooBig => BridgeError::MessageTooBig,
+            EncodeError::FrameTooLarge { size, max } => BridgeError::FrameTooLarge { size, max },
+            EncodeError::FramingMismatch => BridgeError::FramingMismatch,
+        }
     }
 }
 
 #[derive(Debug)]
-pub enum DecodeError {
-    IO(std::io::Error),
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError<E = bincode::error::DecodeError> {
+    IO(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] std::io::Error),
     InvalidFrameSize,
-    Decode(bincode::error::DecodeError),
+    FrameTooLarge { announced: usize, max: usize },
+    ChecksumMismatch,
+    Decode(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] E),
 }
 
-impl From<std::io::Error> for DecodeError {
+impl<E> From<std::io::Error> for DecodeError<E> {
     fn from(err: std::io::Error) -> Self {
         DecodeError::IO(err)
     }
 }
 
-impl<M> Decoder for Codec<M>
+impl<E> fmt::Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::IO(_) => write!(f, "I/O error"),
+            DecodeError::InvalidFrameSize => write!(f, "invalid frame size"),
+            DecodeError::FrameTooLarge { announced, max } => write!(
+                f,
+                "announced frame of {announced} bytes exceeds the maximum of {max} bytes"
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "frame checksum mismatch"),
+            DecodeError::Decode(_) => write!(f, "failed to decode message"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for DecodeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::IO(err) => Some(err),
+            DecodeError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<DecodeError<E>> for BridgeError
 where
-    M: bincode::Decode,
+    BridgeError: From<E>,
 {
-    type Item = M;
+    fn from(err: DecodeError<E>) -> Self {
+        match err {
+            DecodeError::IO(err) => BridgeError::IO(err),
+            DecodeError::InvalidFrameSize => BridgeError::InvalidFrameSize,
+            DecodeError::FrameTooLarge { announced, max } => BridgeError::FrameTooLarge {
+                size: announced,
+                max,
+            },
+            DecodeError::ChecksumMismatch => BridgeError::ChecksumMismatch,
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
+
+/// A frame whose payload could not be decoded. See [`Recovering`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidMessage<E = bincode::error::DecodeError> {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub error: E,
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub payload: Bytes,
+}
+
+impl<E> fmt::Display for InvalidMessage<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to decode message of {} bytes",
+            self.payload.len()
+        )
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for InvalidMessage<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<E> From<InvalidMessage<E>> for BridgeError
+where
+    BridgeError: From<E>,
+{
+    fn from(err: InvalidMessage<E>) -> Self {
+        err.error.into()
+    }
+}
+
+/// Drops skipped bytes from `src` and finds the next complete frame at its start.
+///
+/// Returns the range of the payload and the length of the frame.
+fn next_frame<Tx, Rx, C, E>(
+    codec: &mut Codec<Tx, Rx, C>,
+    src: &mut BytesMut,
+) -> Result<Option<(Range<usize>, usize)>, DecodeError<E>> {
+    loop {
+        match codec.framing().decode_frame(src)? {
+            Decoded::Frame { payload, len } => return Ok(Some((payload, len))),
+            Decoded::Skip { len, skipped } => {
+                codec.record_skip(len, skipped);
+
+                src.advance(len);
+            }
+            Decoded::Incomplete { frame_len } => {
+                if let Some(frame_len) = frame_len {
+                    src.reserve(frame_len - src.len());
+                }
+
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl<Tx, Rx, C> Decoder for Codec<Tx, Rx, C>
+where
+    C: Deserializer<Rx>,
+{
+    type Item = Rx;
+    type Error = DecodeError<C::Error>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let message = self
+            .config()
+            .deserialize(&src[payload])
+            .map_err(DecodeError::Decode)?;
+
+        src.advance(len);
+
+        Ok(Some(message))
+    }
+}
+
+impl Decoder for Codec<RawFrame, RawFrame, Raw> {
+    type Item = Bytes;
+    type Error = DecodeError<Infallible>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let frame = src.split_to(len).freeze();
+
+        Ok(Some(frame.slice(payload)))
+    }
+}
+
+impl<Tx, M, E, I, L> Decoder
+    for Codec<Tx, Borrowed<M, Configuration<E, I, L>>, Configuration<E, I, L>>
+where
+    M: BorrowMessage,
+    Configuration<E, I, L>: Config,
+{
+    type Item = Borrowed<M, Configuration<E, I, L>>;
     type Error = DecodeError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if src.len() < 4 {
-            return Ok(None);
-        }
+        let (payload, len) = match next_frame(self, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
 
-        let packet_size = u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize;
+        let frame = src.split_to(len).freeze();
 
-        if src.len() < packet_size {
-            src.reserve(packet_size - src.len());
+        Ok(Some(Borrowed::new(frame.slice(payload), *self.config())))
+    }
+}
 
-            return Ok(None);
-        }
+impl<Tx, Rx, C> Decoder for Recovering<Codec<Tx, Rx, C>>
+where
+    C: Deserializer<Rx>,
+{
+    type Item = Result<Rx, InvalidMessage<C::Error>>;
+    type Error = DecodeError<C::Error>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let codec = self.inner_mut();
+
+        let (payload, len) = match next_frame(codec, src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let frame = src.split_to(len).freeze();
 
-        if packet_size < 4 {
-            return Err(DecodeError::InvalidFrameSize);
+        match codec.config().deserialize(&frame[payload.clone()]) {
+            Ok(message) => Ok(Some(Ok(message))),
+            Err(error) => Ok(Some(Err(InvalidMessage {
+                error,
+                payload: frame.slice(payload),
+            }))),
         }
+    }
+}
 
-        let message_buf = &src[4..packet_size];
-        let message = bincode::decode_from_slice(message_buf, bincode::config::standard())
-            .map_err(DecodeError::Decode)?;
+impl<T, I> Encoder<I> for Recovering<T>
+where
+    T: Encoder<I>,
+{
+    type Error = T::Error;
 
-        src.advance(packet_size);
+    fn encode(&mut self, item: I, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.inner_mut().encode(item, dst)
+    }
+}
 
-        Ok(Some(message.0))
+impl<T> Decoder for ByRef<T>
+where
+    T: Decoder,
+{
+    type Item = T::Item;
+    type Error = T::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.inner_mut().decode(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.inner_mut().decode_eof(src)
+    }
+}
+
+impl<E> From<frame::DecodeError> for DecodeError<E> {
+    fn from(err: frame::DecodeError) -> Self {
+        match err {
+            frame::DecodeError::InvalidFrameSize => DecodeError::InvalidFrameSize,
+            frame::DecodeError::FrameTooLarge { announced, max } => {
+                DecodeError::FrameTooLarge { announced, max }
+            }
+            frame::DecodeError::ChecksumMismatch => DecodeError::ChecksumMismatch,
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use futures::{stream, SinkExt, StreamExt};
-    use tokio_util::codec::{FramedRead, FramedWrite};
+    use tokio_util::{
+        bytes::{Bytes, BytesMut},
+        codec::{Decoder, Encoder, FramedRead, FramedWrite, LengthDelimitedCodec},
+    };
 
+    use super::{DecodeError, EncodeError};
     use crate::{
+        borrowed::Borrowed,
+        checksum::Checksum,
         codec::Codec,
-        test::{test_messages, TestMessage},
+        frame::{ByteOrder, Framing, LengthPrefix},
+        test::{test_messages, z_test_message, BorrowedTestMessage, TestMessage},
     };
 
     #[tokio::test]
@@ -137,4 +520,526 @@ mod test {
 
         assert_eq!(collected_items, items);
     }
+
+    #[cfg(feature = "postcard")]
+    #[tokio::test]
+    async fn postcard_sink_stream() {
+        use crate::postcard::Postcard;
+
+        let framing = Framing::cobs().with_checksum(Checksum::Crc16);
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec =
+                Codec::<TestMessage, TestMessage, Postcard>::postcard().with_framing(framing);
+            let mut framed_write = FramedWrite::new(write, codec);
+
+            framed_write
+                .send_all(&mut stream::iter(test_messages().into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage, TestMessage, Postcard>::postcard().with_framing(framing);
+        let framed_read = FramedRead::new(read, codec);
+
+        let collected_items: Vec<_> = framed_read.map(Result::unwrap).collect().await;
+
+        handle.await.unwrap();
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn decode_frame_too_large() {
+        let mut codec = Codec::<TestMessage>::new().with_max_frame_size(1024);
+
+        let mut src = BytesMut::from(&u32::MAX.to_be_bytes()[..]);
+
+        let result = codec.decode(&mut src);
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::FrameTooLarge {
+                announced,
+                max: 1024
+            }) if announced == u32::MAX as usize
+        ));
+        assert!(src.capacity() < 1024);
+    }
+
+    #[test]
+    fn encode_frame_too_large() {
+        let mut codec = Codec::<TestMessage>::new().with_max_frame_size(8);
+
+        let mut dst = BytesMut::new();
+
+        let result = codec.encode(z_test_message(), &mut dst);
+
+        assert!(matches!(
+            result,
+            Err(EncodeError::FrameTooLarge { max: 8, .. })
+        ));
+        assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn length_delimited_interop() {
+        let mut codec = Codec::<TestMessage>::new().with_framing(Framing::length_delimited());
+        let mut length_delimited = LengthDelimitedCodec::new();
+
+        let mut buf = BytesMut::new();
+
+        for item in test_messages() {
+            codec.encode(item, &mut buf).unwrap();
+        }
+
+        let mut relayed = BytesMut::new();
+
+        while let Some(payload) = length_delimited.decode(&mut buf).unwrap() {
+            length_delimited
+                .encode(payload.freeze(), &mut relayed)
+                .unwrap();
+        }
+
+        let mut collected_items = Vec::new();
+
+        while let Some(item) = codec.decode(&mut relayed).unwrap() {
+            collected_items.push(item);
+        }
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn little_endian_u16_prefix() {
+        let mut codec = Codec::<TestMessage>::new().with_framing(
+            Framing::new()
+                .with_length_prefix(LengthPrefix::U16)
+                .with_byte_order(ByteOrder::LittleEndian),
+        );
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(TestMessage::A(100), &mut buf).unwrap();
+
+        assert_eq!(&buf[..], &[4, 0, 0, 100]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::A(100)));
+    }
+
+    #[test]
+    fn fixed_int_big_endian_config() {
+        let mut codec = Codec::<TestMessage, TestMessage, _>::with_config(
+            bincode::config::standard()
+                .with_fixed_int_encoding()
+                .with_big_endian(),
+        );
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(TestMessage::B(100), &mut buf).unwrap();
+
+        assert_eq!(&buf[..], &[0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0, 100]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::B(100)));
+    }
+
+    #[test]
+    fn config_limit() {
+        let mut codec = Codec::<TestMessage>::new();
+        let mut limited_codec = Codec::<TestMessage, TestMessage, _>::with_config(
+            bincode::config::standard().with_limit::<16>(),
+        );
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(z_test_message(), &mut buf).unwrap();
+
+        let result = limited_codec.decode(&mut buf);
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::Decode(
+                bincode::error::DecodeError::LimitExceeded
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn crc32_sink_stream() {
+        let framing = Framing::new().with_checksum(Checksum::Crc32);
+
+        let items = test_messages();
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec = Codec::<TestMessage>::new().with_framing(framing);
+            let mut framed_write = FramedWrite::new(write, codec);
+
+            framed_write
+                .send_all(&mut stream::iter(items.into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage>::new().with_framing(framing);
+        let framed_read = FramedRead::new(read, codec);
+
+        let collected_items: Vec<_> = framed_read
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        handle.await.unwrap();
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut codec =
+            Codec::<TestMessage>::new().with_framing(Framing::new().with_checksum(Checksum::Crc32));
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(TestMessage::C(100, 100), &mut buf).unwrap();
+
+        buf[5] ^= 0x80;
+
+        let result = codec.decode(&mut buf);
+
+        assert!(matches!(result, Err(DecodeError::ChecksumMismatch)));
+    }
+
+    #[tokio::test]
+    async fn cobs_sink_stream() {
+        let items = test_messages();
+
+        let (read, write) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let codec = Codec::<TestMessage>::new().with_framing(Framing::cobs());
+            let mut framed_write = FramedWrite::new(write, codec);
+
+            framed_write
+                .send_all(&mut stream::iter(items.into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+            framed_write.close().await.unwrap();
+        });
+
+        let codec = Codec::<TestMessage>::new().with_framing(Framing::cobs());
+        let framed_read = FramedRead::new(read, codec);
+
+        let collected_items: Vec<_> = framed_read
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        handle.await.unwrap();
+
+        assert_eq!(collected_items, test_messages());
+    }
+
+    #[test]
+    fn cobs_resynchronizes() {
+        let mut codec = Codec::<TestMessage>::new()
+            .with_framing(Framing::cobs().with_checksum(Checksum::Crc32));
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(TestMessage::A(100), &mut buf).unwrap();
+
+        // A byte of the first frame is lost on the way.
+        let mut buf = BytesMut::from_iter(buf[..2].iter().chain(&buf[3..]).copied());
+
+        codec.encode(z_test_message(), &mut buf).unwrap();
+
+        // Line noise between two frames.
+        buf.extend_from_slice(&[0x11, 0x22, 0x00]);
+        codec.encode(TestMessage::H, &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(z_test_message()));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::H));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.dropped_frames(), 2);
+    }
+
+    #[test]
+    fn cobs_skips_oversized_frame() {
+        let mut codec = Codec::<TestMessage>::new()
+            .with_framing(Framing::cobs())
+            .with_max_frame_size(16);
+
+        let mut buf = BytesMut::new();
+
+        // A delimited frame over the limit, then a run over the limit without a delimiter yet.
+        buf.extend_from_slice(&[0x11; 20]);
+        buf.extend_from_slice(&[0x00]);
+        codec.encode(TestMessage::A(100), &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::A(100)));
+        assert_eq!(codec.dropped_frames(), 1);
+
+        buf.extend_from_slice(&[0x11; 20]);
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+
+        buf.extend_from_slice(&[0x00]);
+        codec.encode(TestMessage::H, &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::H));
+    }
+
+    #[test]
+    fn sync_word_resynchronizes() {
+        let mut codec = Codec::<TestMessage>::new().with_framing(
+            Framing::new()
+                .with_sync_word(b"\xA5\x5A")
+                .with_checksum(Checksum::Crc16)
+                .with_max_frame_size(1024),
+        );
+
+        let mut buf = BytesMut::new();
+
+        // Line noise before the first frame.
+        buf.extend_from_slice(&[0x11, 0xA5, 0x22]);
+        codec.encode(TestMessage::A(100), &mut buf).unwrap();
+
+        // A frame with a corrupted length prefix.
+        let corrupted = buf.len();
+        codec.encode(z_test_message(), &mut buf).unwrap();
+        buf[corrupted + 2] = 0xFF;
+
+        let corrupted_len = buf.len() - corrupted;
+        codec.encode(TestMessage::H, &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::A(100)));
+        assert_eq!(codec.skipped_bytes(), 3);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(TestMessage::H));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.skipped_bytes(), 3 + corrupted_len);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn recovering_skips_invalid_message() {
+        let mut codec = Codec::<TestMessage>::new().recovering();
+
+        let mut buf = BytesMut::new();
+
+        codec.encode(TestMessage::A(100), &mut buf).unwrap();
+        // A variant this side does not know about.
+        Codec::<u32>::new().encode(100, &mut buf).unwrap();
+        codec.encode(TestMessage::H, &mut buf).unwrap();
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap().unwrap(),
+            TestMessage::A(100)
+        );
+
+        let invalid = codec.decode(&mut buf).unwrap().unwrap().unwrap_err();
+
+        assert!(matches!(
+            invalid.error,
+            bincode::error::DecodeError::UnexpectedVariant { found: 100, .. }
+        ));
+        assert_eq!(&invalid.payload[..], &[100]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap().unwrap(),
+            TestMessage::H
+        );
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn borrowed_decode() {
+        let mut codec =
+            Codec::<BorrowedTestMessage<'static>, Borrowed<BorrowedTestMessage<'static>>>::new();
+
+        let mut buf = BytesMut::new();
+
+        codec
+            .encode(BorrowedTestMessage::Text("Hello"), &mut buf)
+            .unwrap();
+        codec
+            .encode(BorrowedTestMessage::Blob(7, &[1, 2, 3]), &mut buf)
+            .unwrap();
+
+        let first = codec.decode(&mut buf).unwrap().unwrap();
+        let second = codec.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(first.message().unwrap(), BorrowedTestMessage::Text("Hello"));
+        assert_eq!(
+            second.message().unwrap(),
+            BorrowedTestMessage::Blob(7, &[1, 2, 3])
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn separate_directions() {
+        let mut client = Codec::<TestMessage, u32>::new();
+        let mut server = Codec::<u32, TestMessage>::new();
+
+        let mut buf = BytesMut::new();
+
+        client.encode(TestMessage::B(100), &mut buf).unwrap();
+
+        assert_eq!(server.decode(&mut buf).unwrap(), Some(TestMessage::B(100)));
+
+        server.encode(7, &mut buf).unwrap();
+
+        assert_eq!(client.decode(&mut buf).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn encode_by_reference() {
+        let mut codec = Codec::<TestMessage>::new();
+        let mut by_ref = Codec::<TestMessage>::new().by_ref();
+
+        let message = z_test_message();
+
+        let mut by_value = BytesMut::new();
+        let mut by_reference = BytesMut::new();
+
+        codec.encode(message.clone(), &mut by_value).unwrap();
+        by_ref.encode(&message, &mut by_reference).unwrap();
+
+        assert_eq!(by_value, by_reference);
+        assert_eq!(by_ref.decode(&mut by_reference).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn appends_frames() {
+        let mut codec = Codec::<TestMessage>::new();
+
+        let message = z_test_message();
+        let message_size = codec.encoded_len(&message).unwrap();
+
+        let mut dst = BytesMut::from(&b"head"[..]);
+
+        codec.encode(message.clone(), &mut dst).unwrap();
+
+        assert_eq!(dst.len(), 4 + 4 + message_size);
+
+        let _ = dst.split_to(4);
+
+        assert_eq!(codec.decode(&mut dst).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn raw_frame_too_large() {
+        let mut proxy = Codec::raw().with_max_frame_size(8);
+
+        let mut dst = BytesMut::new();
+
+        let result = proxy.encode(Bytes::from_static(&[0; 16]), &mut dst);
+
+        // Refused before anything is reserved.
+        assert!(matches!(
+            result,
+            Err(EncodeError::FrameTooLarge { max: 8, .. })
+        ));
+        assert_eq!(dst.capacity(), 0);
+    }
+
+    #[test]
+    fn encoded_frame_fan_out() {
+        let framing = Framing::new().with_checksum(Checksum::Crc16);
+
+        let frame = Codec::<TestMessage>::new()
+            .with_framing(framing)
+            .encode_frame(&z_test_message())
+            .unwrap();
+
+        for _ in 0..3 {
+            let mut codec = Codec::<TestMessage>::new().with_framing(framing).by_ref();
+
+            let mut buf = BytesMut::new();
+
+            codec.encode(frame.clone(), &mut buf).unwrap();
+
+            assert_eq!(&buf[..], &frame.bytes()[..]);
+            assert_eq!(codec.decode(&mut buf).unwrap(), Some(z_test_message()));
+        }
+
+        let mut codec = Codec::<TestMessage>::new().by_ref();
+
+        let mut buf = BytesMut::new();
+
+        let result = codec.encode(frame, &mut buf);
+
+        assert!(matches!(result, Err(EncodeError::FramingMismatch)));
+        assert!(buf.is_empty());
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn raw_frame_postcard() {
+        let mut device = Codec::<TestMessage, TestMessage, _>::postcard();
+        let mut proxy = Codec::raw();
+
+        let mut buf = BytesMut::new();
+
+        device.encode(z_test_message(), &mut buf).unwrap();
+
+        let payload = proxy.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(device.decode_payload(&payload).unwrap(), z_test_message());
+    }
+
+    #[test]
+    fn raw_frame_forwarding() {
+        let mut device = Codec::<TestMessage>::new();
+        let mut proxy = Codec::raw();
+        let mut backend =
+            Codec::<TestMessage>::new().with_framing(Framing::new().with_checksum(Checksum::Crc32));
+        let mut backend_proxy =
+            Codec::raw().with_framing(Framing::new().with_checksum(Checksum::Crc32));
+
+        let mut inbound = BytesMut::new();
+        let mut outbound = BytesMut::new();
+
+        for item in test_messages() {
+            device.encode(item, &mut inbound).unwrap();
+        }
+
+        while let Some(payload) = proxy.decode(&mut inbound).unwrap() {
+            backend_proxy.encode(payload, &mut outbound).unwrap();
+        }
+
+        let mut collected_items = Vec::new();
+
+        while let Some(item) = backend.decode(&mut outbound).unwrap() {
+            collected_items.push(item);
+        }
+
+        assert_eq!(collected_items, test_messages());
+
+        let mut buf = [0_u8; 128];
+
+        let size = device.encode_payload(&z_test_message(), &mut buf).unwrap();
+
+        proxy
+            .encode(Bytes::copy_from_slice(&buf[..size]), &mut inbound)
+            .unwrap();
+
+        let payload = proxy.decode(&mut inbound).unwrap().unwrap();
+
+        assert_eq!(device.decode_payload(&payload).unwrap(), z_test_message());
+    }
 }
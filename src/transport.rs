@@ -0,0 +1,256 @@
+//! Backend independent sending and receiving of messages.
+
+use core::{
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::{Stream, TryStream};
+use futures_sink::Sink;
+
+/// Sends `Tx` and receives `Rx` messages, regardless of the backend underneath.
+///
+/// Implemented for every [`Stream`] of results that is also a [`Sink`], like tokio's `Framed`,
+/// and for [`Transport`], which joins a separate stream and sink, like the halves of cody-c or
+/// tokio's `FramedRead` and `FramedWrite`.
+///
+/// The returned futures are not required to be `Send`, so that transports on single threaded
+/// embedded executors qualify.
+///
+/// ```
+/// use the_bridge::MessageTransport;
+///
+/// #[derive(bincode::Encode, bincode::Decode)]
+/// enum Request {
+///     Ping(u32),
+/// }
+///
+/// #[derive(bincode::Encode, bincode::Decode)]
+/// enum Response {
+///     Pong(u32),
+/// }
+///
+/// async fn answer_pings<T>(transport: &mut T) -> Result<(), T::SendError>
+/// where
+///     T: MessageTransport<Response, Request>,
+/// {
+///     while let Ok(Some(Request::Ping(n))) = transport.recv().await {
+///         transport.send(Response::Pong(n)).await?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub trait MessageTransport<Tx, Rx> {
+    type SendError;
+    type RecvError;
+
+    /// Sends `message` and flushes it.
+    fn send(&mut self, message: Tx) -> impl Future<Output = Result<(), Self::SendError>>;
+
+    /// Receives the next message.
+    ///
+    /// Returns `Ok(None)` once the peer has closed the connection.
+    fn recv(&mut self) -> impl Future<Output = Result<Option<Rx>, Self::RecvError>>;
+}
+
+impl<T, Tx, Rx> MessageTransport<Tx, Rx> for T
+where
+    T: TryStream<Ok = Rx> + Sink<Tx> + Unpin,
+{
+    type SendError = <T as Sink<Tx>>::Error;
+    type RecvError = <T as TryStream>::Error;
+
+    async fn send(&mut self, message: Tx) -> Result<(), Self::SendError> {
+        poll_fn(|cx| Sink::<Tx>::poll_ready(Pin::new(&mut *self), cx)).await?;
+        Sink::<Tx>::start_send(Pin::new(&mut *self), message)?;
+        poll_fn(|cx| Sink::<Tx>::poll_flush(Pin::new(&mut *self), cx)).await
+    }
+
+    fn recv(&mut self) -> impl Future<Output = Result<Option<Rx>, Self::RecvError>> {
+        poll_fn(|cx| TryStream::try_poll_next(Pin::new(&mut *self), cx).map(Option::transpose))
+    }
+}
+
+/// A [`Stream`] and a [`Sink`] joined into one, to be used as a [`MessageTransport`].
+///
+/// Streams and sinks that are not [`Unpin`] can be pinned first, e.g. with `futures::pin_mut!`.
+pub struct Transport<R, W> {
+    stream: R,
+    sink: W,
+}
+
+impl<R, W> Transport<R, W> {
+    #[inline]
+    pub const fn new(stream: R, sink: W) -> Self {
+        Self { stream, sink }
+    }
+
+    #[inline]
+    pub const fn stream(&self) -> &R {
+        &self.stream
+    }
+
+    #[inline]
+    pub fn stream_mut(&mut self) -> &mut R {
+        &mut self.stream
+    }
+
+    #[inline]
+    pub const fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    #[inline]
+    pub fn sink_mut(&mut self) -> &mut W {
+        &mut self.sink
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> (R, W) {
+        (self.stream, self.sink)
+    }
+}
+
+impl<R, W> Stream for Transport<R, W>
+where
+    R: Stream + Unpin,
+    W: Unpin,
+{
+    type Item = R::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().stream).poll_next(cx)
+    }
+}
+
+impl<R, W, I> Sink<I> for Transport<R, W>
+where
+    R: Unpin,
+    W: Sink<I> + Unpin,
+{
+    type Error = W::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sink).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().sink).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sink).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().sink).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::MessageTransport;
+    use crate::test::{test_messages, TestMessage};
+
+    /// Written once, run over every backend below.
+    async fn echo<T>(transport: &mut T)
+    where
+        T: MessageTransport<TestMessage, TestMessage>,
+        T::SendError: core::fmt::Debug,
+        T::RecvError: core::fmt::Debug,
+    {
+        while let Some(message) = transport.recv().await.unwrap() {
+            transport.send(message).await.unwrap();
+        }
+    }
+
+    async fn exchange<T>(transport: &mut T)
+    where
+        T: MessageTransport<TestMessage, TestMessage>,
+        T::SendError: core::fmt::Debug,
+        T::RecvError: core::fmt::Debug,
+    {
+        for message in test_messages() {
+            transport.send(message.clone()).await.unwrap();
+
+            assert_eq!(transport.recv().await.unwrap(), Some(message));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn tokio_framed_and_halves() {
+        use tokio_util::codec::{Framed, FramedRead, FramedWrite};
+
+        use super::Transport;
+        use crate::codec::Codec;
+
+        let (client, server) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let (read, write) = tokio::io::split(server);
+
+            let mut transport = Transport::new(
+                FramedRead::new(read, Codec::<TestMessage>::new()),
+                FramedWrite::new(write, Codec::<TestMessage>::new()),
+            );
+
+            echo(&mut transport).await;
+        });
+
+        let mut transport = Framed::new(client, Codec::<TestMessage>::new());
+
+        exchange(&mut transport).await;
+
+        drop(transport);
+
+        handle.await.unwrap();
+    }
+
+    #[cfg(all(feature = "tokio", feature = "cody-c"))]
+    #[tokio::test]
+    async fn cody_halves() {
+        use cody_c::{tokio::Compat, FramedRead, FramedWrite};
+        use futures::pin_mut;
+        use tokio_util::codec::Framed;
+
+        use super::Transport;
+        use crate::codec::Codec;
+
+        let (client, server) = tokio::io::duplex(16);
+
+        let handle = tokio::spawn(async move {
+            let (read, write) = tokio::io::split(server);
+
+            let mut framed_read = FramedRead::new_with_buffer(
+                Codec::<TestMessage>::new(),
+                Compat::new(read),
+                [0; 128],
+            );
+            let mut framed_write = FramedWrite::new_with_buffer(
+                Codec::<TestMessage>::new(),
+                Compat::new(write),
+                [0; 128],
+            );
+
+            let stream = framed_read.stream();
+            let sink = framed_write.sink::<TestMessage>();
+
+            pin_mut!(stream, sink);
+
+            echo(&mut Transport::new(stream, sink)).await;
+        });
+
+        let mut transport = Framed::new(client, Codec::<TestMessage>::new());
+
+        exchange(&mut transport).await;
+
+        drop(transport);
+
+        handle.await.unwrap();
+    }
+}